@@ -0,0 +1,27 @@
+use super::*;
+
+#[component]
+pub fn AdminBackup() -> Element {
+    if LOGIN_STATE.read().is_none() {
+        login::login_required(Route::AdminBackup {});
+    }
+    let mut result = use_signal(String::new);
+    rsx! {
+        h1 { "backup" }
+        p { "the archive is written into the backups directory of the storage on the server" }
+        button {
+            onclick: move |_| {
+                spawn(async move {
+                    match send_message::<String>(FrontMessage::CreateBackup).await {
+                        Ok(name) => result.set(format!("backing up into {name}")),
+                        Err(err) => result.set(format!("{err}")),
+                    }
+                });
+            },
+            "start backup"
+        }
+        if !result.read().is_empty() {
+            p { "{result}" }
+        }
+    }
+}
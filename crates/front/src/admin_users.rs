@@ -0,0 +1,148 @@
+use super::*;
+use shared::user::{Role, UserList, UserQuery, UserSummary};
+
+/// Sends an action on a user and reloads the list once it went through.
+async fn act(msg: FrontMessage, mut result: Signal<String>, mut refresh: Signal<u64>) {
+    match send_message::<()>(msg).await {
+        Ok(()) => {
+            result.set("done".into());
+            *refresh.write() += 1;
+        }
+        Err(err) => result.set(format!("{err}")),
+    }
+}
+
+#[component]
+fn user_row(user: UserSummary, result: Signal<String>, refresh: Signal<u64>) -> Element {
+    let mut password = use_signal(String::new);
+    let UserSummary {
+        uid,
+        username,
+        nickname,
+        email,
+        privilege,
+        created_time,
+    } = user;
+    let created = format_time(created_time);
+    let roles: Vec<_> = privilege
+        .roles
+        .iter()
+        .map(|role| role.to_string())
+        .collect();
+    let roles = roles.join(", ");
+    let actions = Role::ALL.map(|role| {
+        let granted = privilege.roles.contains(&role);
+        let label = match (role, granted) {
+            (Role::Banned, false) => "ban".into(),
+            (Role::Banned, true) => "unban".into(),
+            (role, false) => format!("grant {role}"),
+            (role, true) => format!("revoke {role}"),
+        };
+        let msg = if granted {
+            FrontMessage::RevokeRole(uid, role)
+        } else {
+            FrontMessage::GrantRole(uid, role)
+        };
+        (label, msg)
+    });
+    rsx! {
+        hr {}
+        p {
+            Link { to: Route::UserProfile { uid }, "#{uid} {nickname}" }
+            " {username} {email} registered {created}"
+        }
+        p { "roles: {roles}" }
+        for (label , msg) in actions {
+            button {
+                onclick: move |_| {
+                    spawn(act(msg.clone(), result, refresh));
+                },
+                "{label}"
+            }
+        }
+        button {
+            onclick: move |_| {
+                spawn(act(FrontMessage::ForceLogout(uid), result, refresh));
+            },
+            "force logout"
+        }
+        input {
+            r#type: "password",
+            placeholder: "new password",
+            onchange: move |evt| {
+                password.set(evt.value());
+            },
+        }
+        button {
+            onclick: move |_| {
+                let msg = FrontMessage::ResetPassword(uid, password.cloned().into());
+                spawn(act(msg, result, refresh));
+            },
+            "reset password"
+        }
+    }
+}
+
+#[component]
+pub fn AdminUsers() -> Element {
+    if LOGIN_STATE.read().is_none() {
+        login::login_required(Route::AdminUsers {});
+    }
+    let mut search = use_signal(String::new);
+    let mut index = use_signal(|| 0u64);
+    let result = use_signal(String::new);
+    let refresh = use_signal(|| 0u64);
+    let list = use_resource(move || async move {
+        refresh();
+        let query = UserQuery {
+            search: search.cloned().into(),
+            index: index(),
+        };
+        send_message::<UserList>(FrontMessage::ListUsers(query)).await
+    });
+    let content = match &*list.read() {
+        Some(Ok(UserList { users, page_count })) => {
+            let (users, count) = (users.clone(), *page_count);
+            let current = index();
+            rsx! {
+                for user in users {
+                    user_row { key: "{user.uid}", user, result, refresh }
+                }
+                hr {}
+                div {
+                    button {
+                        disabled: current == 0,
+                        onclick: move |_| index -= 1,
+                        "<"
+                    }
+                    label { "{current + 1}/{count}" }
+                    button {
+                        disabled: current + 1 >= count,
+                        onclick: move |_| index += 1,
+                        ">"
+                    }
+                }
+            }
+        }
+        Some(Err(err)) => rsx! {
+            p { "{err}" }
+        },
+        None => rsx! {
+            p { "loading" }
+        },
+    };
+    rsx! {
+        h1 { "users" }
+        input {
+            placeholder: "username or email",
+            onchange: move |evt| {
+                search.set(evt.value());
+                index.set(0);
+            },
+        }
+        if !result.read().is_empty() {
+            p { "{result}" }
+        }
+        {content}
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
-use shared::judge::JudgeMachineSignal;
+use shared::judge::{JudgeMachineSignal, JudgeTask, QueuedTask};
+use shared::user::Permission;
 
 #[component]
 fn display_single(sig: JudgeMachineSignal) -> Element {
@@ -7,12 +8,15 @@ fn display_single(sig: JudgeMachineSignal) -> Element {
         cpu_usage,
         cpu_name,
         tasks,
+        runs,
+        capacity,
+        cpu_benchmark,
         uuid,
         timestamp: _,
         hostname,
         system_name,
     } = sig;
-    let len = tasks.len();
+    let len = tasks.len() + runs.len();
     let system_name = system_name.as_deref().unwrap_or("null");
     let hostname = hostname.as_deref().unwrap_or("null");
     rsx! {
@@ -21,7 +25,8 @@ fn display_single(sig: JudgeMachineSignal) -> Element {
         p { "{system_name}" }
         p { "{hostname}" }
         p { "CPU usage {cpu_usage}%" }
-        p { "tasks {len}" }
+        p { "CPU benchmark {cpu_benchmark}" }
+        p { "tasks {len}/{capacity}" }
         hr {}
     }
 }
@@ -41,6 +46,36 @@ fn display_signals(judge_signals: Vec<JudgeMachineSignal>) -> Element {
     }
 }
 
+#[component]
+fn display_queue() -> Element {
+    let queue_res = use_resource(|| async {
+        send_message::<Vec<QueuedTask>>(FrontMessage::GetJudgeQueue).await
+    });
+    match &*queue_res.read() {
+        Some(Ok(queue)) if queue.is_empty() => rsx! {
+            p { "judge queue is empty" }
+        },
+        Some(Ok(queue)) => rsx! {
+            p { "judge queue" }
+            for QueuedTask { task, uid, priority } in queue.iter().copied() {
+                p {
+                    match task {
+                        JudgeTask::Record(rid) => rsx! {
+                            Link { to: Route::Record { rid }, "{rid}" }
+                        },
+                        JudgeTask::Run(id) => rsx! { "run {id}" },
+                    }
+                    " {priority} user {uid}"
+                }
+            }
+        },
+        Some(Err(_)) => rsx! {},
+        None => rsx! {
+            p { "loading" }
+        },
+    }
+}
+
 #[component]
 pub fn JudgeStatus() -> Element {
     let mut judge_signals_res = use_resource(|| async {
@@ -56,6 +91,13 @@ pub fn JudgeStatus() -> Element {
                 "refresh"
             }
             display_signals { judge_signals: judge_signals.clone() }
+            if LOGIN_STATE
+                .read()
+                .as_ref()
+                .is_some_and(|user| user.privilege.has(Permission::ManageJudges))
+            {
+                display_queue {}
+            }
         }
     } else {
         rsx! {
@@ -6,9 +6,11 @@ use shared::error::ServerError;
 use shared::front::FrontMessage;
 use shared::problem::Pid;
 use shared::record::Rid;
-use shared::user::LoginedUser;
+use shared::user::{LoginedUser, Uid};
 use std::sync::LazyLock;
 
+mod admin_backup;
+mod admin_users;
 mod components;
 mod error;
 mod file_view;
@@ -26,9 +28,13 @@ mod problem_file_download;
 mod problem_file_list;
 mod problem_file_view;
 mod problem_pages;
+mod ranklist;
 mod record;
+mod records;
 mod register;
+mod settings;
 mod submit;
+mod user_profile;
 mod utility;
 
 use components::*;
@@ -59,6 +65,8 @@ enum Route {
     Submit { pid: Pid },
     #[route("/record/:rid")]
     Record { rid: Rid },
+    #[route("/records")]
+    Records {},
     #[route("/judge_status")]
     JudgeStatus {},
     #[route("/login")]
@@ -81,8 +89,20 @@ enum Route {
     ProblemFileList { pid: Pid },
     #[route("/problem_page/:index")]
     ProblemPage { index: u64 },
+    #[route("/settings")]
+    Settings {},
+    #[route("/ranklist/:index")]
+    Ranklist { index: u64 },
+    #[route("/user/:uid")]
+    UserProfile { uid: Uid },
+    #[route("/admin/users")]
+    AdminUsers {},
+    #[route("/admin/backup")]
+    AdminBackup {},
 }
 
+use admin_backup::AdminBackup;
+use admin_users::AdminUsers;
 use file_view::FileView;
 use home::Home;
 use judge_status::JudgeStatus;
@@ -97,9 +117,13 @@ use problem_file_download::ProblemFileDownload;
 use problem_file_list::ProblemFileList;
 use problem_file_view::ProblemFileView;
 use problem_pages::ProblemPage;
+use ranklist::Ranklist;
 use record::Record;
+use records::Records;
 use register::UserRegister;
+use settings::Settings;
 use submit::Submit;
+use user_profile::UserProfile;
 
 #[component]
 fn app() -> Element {
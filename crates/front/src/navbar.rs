@@ -1,4 +1,5 @@
 use super::*;
+use shared::user::Permission;
 
 #[component]
 pub fn Navbar() -> Element {
@@ -6,11 +7,25 @@ pub fn Navbar() -> Element {
         h1 { "MyGoJ" }
         Link { to: Route::Home {}, "Home " }
         Link { to: Route::ProblemPage { index: 0 }, "Problems " }
+        Link { to: Route::Records {}, "Records " }
+        Link { to: Route::Ranklist { index: 0 }, "Ranklist " }
         {
             if let Some(login_state) = &*LOGIN_STATE.read() {
                 let nickname = &login_state.nickname;
+                let uid = login_state.uid;
+                let manage_users = login_state.privilege.has(Permission::ManageUsers);
+                let backup = login_state.privilege.has(Permission::Backup);
                 rsx! {
-                    p { "{nickname}" }
+                    p {
+                        Link { to: Route::UserProfile { uid }, "{nickname}" }
+                    }
+                    Link { to: Route::Settings {}, "Settings " }
+                    if manage_users {
+                        Link { to: Route::AdminUsers {}, "Users " }
+                    }
+                    if backup {
+                        Link { to: Route::AdminBackup {}, "Backup " }
+                    }
                     Link { to: Route::Logout {}, "Logout " }
                 }
             } else {
@@ -1,4 +1,5 @@
 use super::*;
+use shared::problem::{FileIo, Sample};
 pub use shared::problem::ProblemFront;
 
 #[component]
@@ -15,6 +16,44 @@ fn wrong_page() -> Element {
     }
 }
 
+fn copy_to_clipboard(text: &str) {
+    let _ = web_sys::window()
+        .unwrap()
+        .navigator()
+        .clipboard()
+        .write_text(text);
+}
+
+#[component]
+fn render_sample(idx: usize, sample: Sample) -> Element {
+    let Sample { input, output, .. } = sample;
+    let no = idx + 1;
+    rsx! {
+        p {
+            "sample input {no} "
+            button {
+                onclick: {
+                    let input = input.clone();
+                    move |_| copy_to_clipboard(&input)
+                },
+                "copy"
+            }
+        }
+        pre { "{input}" }
+        p {
+            "sample output {no} "
+            button {
+                onclick: {
+                    let output = output.clone();
+                    move |_| copy_to_clipboard(&output)
+                },
+                "copy"
+            }
+        }
+        pre { "{output}" }
+    }
+}
+
 #[component]
 fn render_problem(front: ProblemFront) -> Element {
     tracing::info!("render problem {:?}", &front);
@@ -32,14 +71,26 @@ fn render_problem(front: ProblemFront) -> Element {
         time_limit,
         memory_limit,
         owner_display,
+        run_limits,
+        file_io,
+        samples,
         ..
     } = front;
+    let stack = match run_limits.stack {
+        Some(stack) => format!("{stack} mb"),
+        None => "up to the memory limit".into(),
+    };
+    let processes = run_limits.processes;
     rsx! {
         if let Some(display) = owner_display {
             {
                 let name = display.nickname;
+                let uid = display.uid;
                 rsx! {
-                    label { "owner {name}" }
+                    label {
+                        "owner "
+                        Link { to: Route::UserProfile { uid }, "{name}" }
+                    }
                 }
             }
         }
@@ -61,7 +112,17 @@ fn render_problem(front: ProblemFront) -> Element {
         }
         h1 { "{pid} {title}" }
         p { "time {time_limit} ms memory {memory_limit} mb" }
+        p { "stack {stack}" }
+        if processes > 1 {
+            p { "up to {processes} threads" }
+        }
+        if let Some(FileIo { input, output }) = file_io {
+            p { "read from {input} and write to {output}" }
+        }
         Markdown { content: statement }
+        for (idx , sample) in samples.into_iter().enumerate() {
+            render_sample { idx, sample }
+        }
         Link { to: Route::Submit { pid }, "To submit" }
     }
 }
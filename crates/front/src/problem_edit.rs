@@ -586,6 +586,26 @@ fn render_editable(mut editable: Signal<Option<ProblemEditable>>) -> Element {
     }
 }
 
+#[component]
+fn rejudge_all(pid: Pid) -> Element {
+    let mut message = use_signal(String::new);
+    rsx! {
+        button {
+            onclick: move |_| {
+                let pid = pid.clone();
+                async move {
+                    match send_message::<u64>(FrontMessage::RejudgeProblem(pid)).await {
+                        Ok(count) => message.set(format!("{count} records requeued")),
+                        Err(err) => message.set(format!("{err}")),
+                    }
+                }
+            },
+            "rejudge all records"
+        }
+        label { "{message}" }
+    }
+}
+
 #[component]
 pub fn ProblemEdit(pid: Pid) -> Element {
     use_context_provider(|| pid.clone());
@@ -642,6 +662,8 @@ pub fn ProblemEdit(pid: Pid) -> Element {
                     hr {}
                     render_files_edit { files, evt_groups }
                     hr {}
+                    rejudge_all { pid: pid() }
+                    hr {}
                 }
             } else {
                 rsx! {
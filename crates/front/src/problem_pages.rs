@@ -1,5 +1,6 @@
 use super::*;
 use shared::problem::*;
+use shared::user::Permission;
 
 #[component]
 pub fn Inner(list: Vec<ProblemProfile>, index: u64, count: u64) -> Element {
@@ -63,9 +64,50 @@ pub fn ProblemPage(index: u64) -> Element {
             .unwrap();
         list
     });
+    let can_create = LOGIN_STATE
+        .read()
+        .as_ref()
+        .is_some_and(|user| user.privilege.has(Permission::CreateProblems));
     rsx! {
         if let Some(count) = count() && let Some(list) = list() {
             Inner { list, index, count }
         }
+        if can_create {
+            CreateProblem {}
+        }
+    }
+}
+
+#[component]
+fn CreateProblem() -> Element {
+    let mut pid = use_signal(String::new);
+    let mut error = use_signal(String::new);
+    rsx! {
+        div {
+            input {
+                placeholder: "pid",
+                value: pid(),
+                onchange: move |evt| {
+                    pid.set(evt.value());
+                },
+            }
+            button {
+                onclick: move |_| {
+                    spawn(async move {
+                        let new = Pid::new(&pid());
+                        match send_message::<()>(FrontMessage::CreateProblem(new.clone())).await {
+                            Ok(()) => {
+                                navigator().push(Route::ProblemEdit { pid: new });
+                            }
+                            Err(err) => error.set(format!("{err}")),
+                        }
+                    });
+                },
+                "create problem"
+            }
+            if !error.read().is_empty() {
+                p { "{error}" }
+            }
+        }
     }
 }
@@ -0,0 +1,66 @@
+use super::*;
+use shared::user::RankEntry;
+
+#[component]
+fn Inner(list: Vec<RankEntry>, index: u64, count: u64) -> Element {
+    rsx! {
+        for RankEntry { rank, user, solved, submissions } in list {
+            {
+                let uid = user.uid;
+                let nickname = user.nickname;
+                rsx! {
+                    p {
+                        "{rank} "
+                        Link { to: Route::UserProfile { uid }, "{nickname}" }
+                        " solved {solved} submissions {submissions}"
+                    }
+                }
+            }
+        }
+        div {
+            if index > 0 {
+                Link {
+                    to: Route::Ranklist {
+                        index: index - 1,
+                    },
+                    "<"
+                }
+            } else {
+                a { "<" }
+            }
+            label { "{index + 1}/{count}" }
+            if index + 1 < count {
+                Link {
+                    to: Route::Ranklist {
+                        index: index + 1,
+                    },
+                    ">"
+                }
+            } else {
+                a { ">" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Ranklist(index: u64) -> Element {
+    let count = use_resource(|| async move {
+        let count: u64 = send_message(FrontMessage::GetRanklistPageCount)
+            .await
+            .unwrap();
+        count
+    });
+    let list = use_resource(move || async move {
+        let list: Vec<RankEntry> = send_message(FrontMessage::GetRanklistPage(index))
+            .await
+            .unwrap();
+        list
+    });
+    rsx! {
+        h1 { "Ranklist" }
+        if let Some(count) = count() && let Some(list) = list() {
+            Inner { list, index, count }
+        }
+    }
+}
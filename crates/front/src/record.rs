@@ -1,22 +1,124 @@
 use super::*;
 
 mod inner {
-    use super::send_message;
-    use crate::ws_origin;
+    use super::{send_message, sleep};
+    use crate::{LOGIN_STATE, ws_origin};
     use dioxus::logger::tracing;
     use dioxus::prelude::*;
     use futures_util::StreamExt;
     use shared::front::FrontMessage;
-    use shared::judge::{AllJudgeResult, SingleJudgeResult};
+    use shared::judge::{AllJudgeResult, SingleJudgeResult, Verdict};
+    use shared::problem::ProblemFront;
     use shared::record::*;
+    use shared::user::Permission;
     use ws_stream_wasm::*;
 
+    /// Which testcase outputs of a record the user may look into.
+    #[derive(Debug, Clone, PartialEq)]
+    struct OutputAccess {
+        rid: Rid,
+        /// Failed testcases, for managers of the problem.
+        failed: bool,
+        samples: Vec<usize>,
+    }
+
+    impl OutputAccess {
+        fn allows(&self, idx: usize, verdict: Verdict) -> bool {
+            (self.failed && verdict != Verdict::Ac) || self.samples.contains(&idx)
+        }
+    }
+
+    #[component]
+    fn show_case_diff(diff: CaseDiff) -> Element {
+        let CaseDiff {
+            input,
+            output,
+            answer,
+            first_difference,
+        } = diff;
+        let (output_offset, answer_offset) =
+            first_difference.unwrap_or((output.len(), answer.len()));
+        let (output_same, output_rest) = output.split_at(output_offset.min(output.len()));
+        let (answer_same, answer_rest) = answer.split_at(answer_offset.min(answer.len()));
+        rsx! {
+            if let Some(input) = input {
+                p { "input" }
+                pre { "{input}" }
+            }
+            if first_difference.is_none() {
+                p { "no difference in the beginning" }
+            }
+            p { "output" }
+            pre {
+                "{output_same}"
+                span { style: "background-color: #fcc", "{output_rest}" }
+            }
+            p { "answer" }
+            pre {
+                "{answer_same}"
+                span { style: "background-color: #cfc", "{answer_rest}" }
+            }
+        }
+    }
+
+    async fn fetch_case_output(rid: Rid, idx: usize, mut status: Signal<Option<CaseOutputStatus>>) {
+        loop {
+            let ret = send_message::<CaseOutputStatus>(FrontMessage::GetCaseOutput(rid, idx)).await;
+            let Ok(ret) = ret else {
+                status.set(Some(CaseOutputStatus::Unavailable));
+                return;
+            };
+            let requested = ret == CaseOutputStatus::Requested;
+            status.set(Some(ret));
+            if !requested {
+                return;
+            }
+            sleep(500).await;
+        }
+    }
+
     #[component]
-    fn show_all_results(status: Vec<Option<SingleJudgeResult>>) -> Element {
+    fn show_case_output(rid: Rid, idx: usize) -> Element {
+        let status = use_signal(|| None);
+        match status.cloned() {
+            None => rsx! {
+                button {
+                    onclick: move |_| {
+                        spawn(fetch_case_output(rid, idx, status));
+                    },
+                    "show output"
+                }
+            },
+            Some(CaseOutputStatus::Requested) => rsx! {
+                p { "fetching output" }
+            },
+            Some(CaseOutputStatus::Unavailable) => rsx! {
+                p { "output is not available anymore" }
+            },
+            Some(CaseOutputStatus::Ready(diff)) => rsx! {
+                show_case_diff { diff }
+            },
+        }
+    }
+
+    #[component]
+    fn show_all_results(
+        status: Vec<Option<SingleJudgeResult>>,
+        #[props(default)] outputs: Option<OutputAccess>,
+    ) -> Element {
         rsx! {
             for (idx , case) in status.into_iter().enumerate() {
-                if let Some(SingleJudgeResult { verdict, memory_used, time_used }) = case {
-                    p { "#{idx} {verdict} {time_used} ms {memory_used} mb" }
+                if let Some(SingleJudgeResult { verdict, memory_used, time_used, wall_time, termination }) = case {
+                    if let Some(termination) = termination {
+                        p {
+                            "#{idx} {verdict} ({termination}) {time_used} ms (wall {wall_time} ms) {memory_used} mb"
+                        }
+                    } else {
+                        p { "#{idx} {verdict} {time_used} ms (wall {wall_time} ms) {memory_used} mb" }
+                    }
+                    if let Some(rid) = outputs.as_ref().filter(|x| x.allows(idx, verdict)).map(|x| x.rid) {
+                        show_case_output { rid, idx }
+                    }
                 } else {
                     p { "#{idx} Running" }
                 }
@@ -25,7 +127,10 @@ mod inner {
     }
 
     #[component]
-    fn show_record_status(status: RecordStatus) -> Element {
+    fn show_record_status(
+        status: RecordStatus,
+        #[props(default)] outputs: Option<OutputAccess>,
+    ) -> Element {
         match status {
             RecordStatus::Waiting => {
                 rsx! {
@@ -51,13 +156,14 @@ mod inner {
                 rsx! {
                     p { "max time {max_time} sum time {sum_time} memory used {memory_used} " }
                     p { "{verdict}" }
-                    show_all_results { status }
+                    show_all_results { status, outputs }
                 }
             }
             RecordStatus::CompileError(err) => {
+                let kind = format!("{}", err.kind);
                 let err = format!("{}", err);
                 rsx! {
-                    p { "Compile Error" }
+                    p { "{kind}" }
                     textarea { "{err}" }
                 }
             }
@@ -93,6 +199,9 @@ mod inner {
             RecordMessage::Compiling => {
                 *status = RecordStatus::Compiling;
             }
+            RecordMessage::Waiting => {
+                *status = RecordStatus::Waiting;
+            }
         }
         true
     }
@@ -136,20 +245,57 @@ mod inner {
     //     }
     // }
 
+    async fn follow(rid: Rid, mut record: Signal<Option<Record>>) {
+        let t: Record = send_message(FrontMessage::GetRecord(rid)).await.unwrap();
+        let done = t.status.done();
+        record.set(Some(t));
+        if !done {
+            #[cfg(feature = "ws")]
+            ws(rid, record).await;
+            #[cfg(not(feature = "ws"))]
+            manual_refresh(rid, record).await;
+        }
+    }
+
+    async fn rejudge(rid: Rid, record: Signal<Option<Record>>) {
+        if let Err(err) = send_message::<()>(FrontMessage::Rejudge(rid)).await {
+            tracing::error!("fail to rejudge {rid} {err:?}");
+            return;
+        }
+        follow(rid, record).await;
+    }
+
     #[component]
     pub fn record_page(rid: Rid) -> Element {
-        let mut record = use_signal(|| None);
-        use_future(move || async move {
-            let t: Record = send_message(FrontMessage::GetRecord(rid)).await.unwrap();
-            let done = t.status.done();
-            record.set(Some(t));
-            if !done {
-                #[cfg(feature = "ws")]
-                ws(rid, record).await;
-                #[cfg(not(feature = "ws"))]
-                manual_refresh(rid, record).await;
-            }
+        let record = use_signal(|| None);
+        use_future(move || follow(rid, record));
+        let can_rejudge = LOGIN_STATE
+            .read()
+            .as_ref()
+            .is_some_and(|user| user.privilege.has(Permission::Rejudge));
+        let pid = use_memo(move || record.read().as_ref().map(|rec: &Record| rec.pid.clone()));
+        let problem = use_resource(move || async move {
+            let pid = pid()?;
+            send_message::<ProblemFront>(FrontMessage::GetProblemFront(pid))
+                .await
+                .ok()
         });
+        let outputs = match (&*LOGIN_STATE.read(), &*problem.read(), &*record.read()) {
+            (Some(user), Some(Some(problem)), Some(rec)) => {
+                let failed = problem.can_be_edited_by(user);
+                let samples = if failed || rec.uid == user.uid {
+                    rec.samples.clone()
+                } else {
+                    Vec::new()
+                };
+                Some(OutputAccess {
+                    rid,
+                    failed,
+                    samples,
+                })
+            }
+            _ => None,
+        };
         if let Some(record) = &*record.read() {
             let Record {
                 rid: _,
@@ -158,10 +304,24 @@ mod inner {
                 status,
                 ..
             } = record;
+            let done = status.done();
             rsx! {
                 p { "Problem {pid}" }
-                show_record_status { status: status.clone() }
-                textarea { "{code}" }
+                if can_rejudge && done {
+                    button {
+                        onclick: move |_| {
+                            spawn(rejudge(rid, record));
+                        },
+                        "rejudge"
+                    }
+                }
+                show_record_status {
+                    status: status.clone(),
+                    outputs,
+                }
+                if !code.is_empty() {
+                    textarea { "{code}" }
+                }
             }
         } else {
             rsx! {
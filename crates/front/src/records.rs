@@ -0,0 +1,106 @@
+use super::*;
+use shared::record::{RECORDS_PAGE_SIZE, RecordFlag, RecordQuery, RecordSummary};
+
+#[component]
+fn record_list(list: Vec<RecordSummary>) -> Element {
+    rsx! {
+        for RecordSummary { rid, pid, uid, flag, time, max_time, memory_used } in list {
+            {
+                let flag = flag.as_str();
+                let time = format_time(time);
+                let usage = match (max_time, memory_used) {
+                    (Some(max_time), Some(memory_used)) => format!(" {max_time} ms {memory_used} mb"),
+                    _ => String::new(),
+                };
+                rsx! {
+                    p {
+                        Link { to: Route::Record { rid }, "{rid}" }
+                        " "
+                        Link { to: Route::Problem { pid: pid.clone() }, "{pid}" }
+                        " "
+                        Link { to: Route::UserProfile { uid }, "user {uid}" }
+                        " {flag}{usage} {time}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Records() -> Element {
+    let mut pid = use_signal(String::new);
+    let mut uid = use_signal(String::new);
+    let mut flag = use_signal(|| None);
+    let mut days = use_signal(String::new);
+    let mut index = use_signal(|| 0u64);
+    let list = use_resource(move || async move {
+        let query = RecordQuery {
+            pid: Some(pid.cloned()).filter(|pid| !pid.is_empty()).map(|pid| Pid::new(&pid)),
+            uid: uid.read().parse().ok(),
+            flag: flag(),
+            since: days.read().parse::<i64>().ok().map(|days| now() - days * 24 * 60 * 60),
+            index: index(),
+        };
+        send_message::<Vec<RecordSummary>>(FrontMessage::GetRecords(query)).await
+    });
+    let content = match &*list.read() {
+        Some(Ok(records)) => {
+            let full = records.len() as u64 == RECORDS_PAGE_SIZE;
+            let current = index();
+            rsx! {
+                record_list { list: records.clone() }
+                div {
+                    button {
+                        disabled: current == 0,
+                        onclick: move |_| index -= 1,
+                        "<"
+                    }
+                    label { "{current + 1}" }
+                    button { disabled: !full, onclick: move |_| index += 1, ">" }
+                }
+            }
+        }
+        Some(Err(err)) => rsx! {
+            p { "{err}" }
+        },
+        None => rsx! {
+            p { "loading" }
+        },
+    };
+    rsx! {
+        h1 { "records" }
+        input {
+            placeholder: "problem",
+            onchange: move |evt| {
+                pid.set(evt.value());
+                index.set(0);
+            },
+        }
+        input {
+            placeholder: "uid",
+            onchange: move |evt| {
+                uid.set(evt.value());
+                index.set(0);
+            },
+        }
+        select {
+            onchange: move |evt| {
+                flag.set(RecordFlag::parse(&evt.value()));
+                index.set(0);
+            },
+            option { value: "", "any result" }
+            for f in RecordFlag::ALL {
+                option { value: f.as_str(), "{f.as_str()}" }
+            }
+        }
+        input {
+            placeholder: "in the last days",
+            onchange: move |evt| {
+                days.set(evt.value());
+                index.set(0);
+            },
+        }
+        {content}
+    }
+}
@@ -0,0 +1,92 @@
+use super::*;
+use shared::user::PasswordChange;
+
+/// Sends a change and shows how it went, refreshing the login state once it is done.
+async fn apply(msg: FrontMessage, mut result: Signal<String>) {
+    match send_message::<()>(msg).await {
+        Ok(()) => {
+            init_login_state().await;
+            result.set("saved".into());
+        }
+        Err(err) => result.set(format!("{err}")),
+    }
+}
+
+#[component]
+pub fn Settings() -> Element {
+    if LOGIN_STATE.read().is_none() {
+        login::login_required(Route::Settings {});
+    }
+    let mut nickname = use_signal(String::new);
+    let mut email = use_signal(String::new);
+    let mut old_password = use_signal(String::new);
+    let mut new_password = use_signal(String::new);
+    let mut confirm = use_signal(String::new);
+    let mut result = use_signal(String::new);
+
+    rsx! {
+        h1 { "settings" }
+        p { "nickname" }
+        input {
+            onchange: move |evt| {
+                nickname.set(evt.value());
+            },
+        }
+        button {
+            onclick: move |_| {
+                spawn(apply(FrontMessage::ChangeNickname(nickname.cloned().into()), result));
+            },
+            "change nickname"
+        }
+        p { "email" }
+        input {
+            onchange: move |evt| {
+                email.set(evt.value());
+            },
+        }
+        button {
+            onclick: move |_| {
+                spawn(apply(FrontMessage::ChangeEmail(email.cloned().into()), result));
+            },
+            "change email"
+        }
+        p { "old password" }
+        input {
+            r#type: "password",
+            onchange: move |evt| {
+                old_password.set(evt.value());
+            },
+        }
+        p { "new password" }
+        input {
+            r#type: "password",
+            onchange: move |evt| {
+                new_password.set(evt.value());
+            },
+        }
+        p { "confirm" }
+        input {
+            r#type: "password",
+            onchange: move |evt| {
+                confirm.set(evt.value());
+            },
+        }
+        button {
+            onclick: move |_| {
+                if new_password.cloned() != confirm.cloned() {
+                    result.set("password not equal".into());
+                    return;
+                }
+                let change = PasswordChange {
+                    old: old_password.cloned().into(),
+                    new: new_password.cloned().into(),
+                };
+                spawn(apply(FrontMessage::ChangePassword(change), result));
+            },
+            "change password"
+        }
+        if !result.read().is_empty() {
+            p { "{result}" }
+        }
+    }
+}
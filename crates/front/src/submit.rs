@@ -1,12 +1,78 @@
 use super::*;
+use shared::judge::*;
 use shared::submission::*;
 
+#[component]
+fn show_custom_run(status: CustomRunStatus) -> Element {
+    match status {
+        CustomRunStatus::Waiting => rsx! {
+            p { "Waiting" }
+        },
+        CustomRunStatus::Running => rsx! {
+            p { "Running" }
+        },
+        CustomRunStatus::CompileError(err) => {
+            let kind = format!("{}", err.kind);
+            let err = format!("{}", err);
+            rsx! {
+                p { "{kind}" }
+                textarea { "{err}" }
+            }
+        }
+        CustomRunStatus::Finished(output) => {
+            let CustomRunOutput {
+                result:
+                    SingleJudgeResult {
+                        verdict,
+                        memory_used,
+                        time_used,
+                        wall_time,
+                        termination,
+                    },
+                stdout,
+                stderr,
+            } = output;
+            let verdict = match termination {
+                Some(termination) => format!("{verdict} ({termination})"),
+                None => format!("{verdict}"),
+            };
+            rsx! {
+                p { "{verdict} {time_used} ms (wall {wall_time} ms) {memory_used} mb" }
+                p { "stdout" }
+                textarea { readonly: true, "{stdout}" }
+                p { "stderr" }
+                textarea { readonly: true, "{stderr}" }
+            }
+        }
+    }
+}
+
+async fn follow_run(id: RunId, mut status: Signal<Option<CustomRunStatus>>) {
+    loop {
+        let Ok(ret) = send_message::<CustomRunStatus>(FrontMessage::GetCustomRun(id)).await else {
+            // lets the run button be pressed again
+            status.set(None);
+            return;
+        };
+        let done = ret.done();
+        status.set(Some(ret));
+        if done {
+            return;
+        }
+        sleep(500).await;
+    }
+}
+
 #[component]
 pub fn Submit(pid: Pid) -> Element {
     if LOGIN_STATE.read().is_none() {
         login::login_required(Route::Submit { pid: pid.clone() });
     }
     let mut code = use_signal(String::new);
+    let mut stdin = use_signal(String::new);
+    let mut run_status = use_signal(|| None::<CustomRunStatus>);
+    let running = run_status.read().as_ref().is_some_and(|x| !x.done());
+    let run_pid = pid.clone();
     rsx! {
         h1 { "submit to {pid}" }
         Link { to: Route::Problem { pid: pid.clone() }, "back to problem" }
@@ -29,5 +95,32 @@ pub fn Submit(pid: Pid) -> Element {
             },
             "submit"
         }
+        h2 { "run with custom input" }
+        textarea {
+            onchange: move |evt| {
+                stdin.set(evt.value());
+            },
+        }
+        button {
+            disabled: running,
+            onclick: move |_| {
+                let run = CustomRun {
+                    pid: run_pid.clone(),
+                    code: code.cloned(),
+                    stdin: stdin.cloned(),
+                };
+                run_status.set(Some(CustomRunStatus::Waiting));
+                spawn(async move {
+                    match send_message::<RunId>(FrontMessage::StartCustomRun(run)).await {
+                        Ok(id) => follow_run(id, run_status).await,
+                        Err(_) => run_status.set(None),
+                    }
+                });
+            },
+            "run"
+        }
+        if let Some(status) = run_status.cloned() {
+            show_custom_run { status }
+        }
     }
 }
@@ -0,0 +1,92 @@
+use super::*;
+use shared::problem::ProblemProfile;
+use shared::record::RecordSummary;
+use shared::user::UserProfile as Profile;
+
+#[component]
+fn problem_list(list: Vec<Pid>) -> Element {
+    rsx! {
+        p {
+            for pid in list {
+                Link { to: Route::Problem { pid: pid.clone() }, "{pid} " }
+            }
+        }
+    }
+}
+
+#[component]
+fn render_profile(profile: Profile) -> Element {
+    let Profile {
+        display,
+        created_time,
+        accepted,
+        attempted,
+        submissions,
+        flags,
+        recent,
+        owned,
+    } = profile;
+    let nickname = display.nickname;
+    let uid = display.uid;
+    let created_time = format_time(created_time);
+    let accepted_count = accepted.len();
+    let attempted_count = attempted.len();
+    rsx! {
+        h1 { "{nickname}" }
+        p { "uid {uid}, registered at {created_time}" }
+        p { "{submissions} submissions" }
+        for (flag , count) in flags {
+            {
+                let flag = flag.as_str();
+                rsx! {
+                    p { "{flag} {count}" }
+                }
+            }
+        }
+        h2 { "accepted {accepted_count}" }
+        problem_list { list: accepted }
+        h2 { "attempted {attempted_count}" }
+        problem_list { list: attempted }
+        if !owned.is_empty() {
+            h2 { "owned problems" }
+            for ProblemProfile { pid, title } in owned {
+                p {
+                    Link { to: Route::Problem { pid: pid.clone() }, "{pid} {title}" }
+                }
+            }
+        }
+        h2 { "recent submissions" }
+        for RecordSummary { rid, pid, flag, time, .. } in recent {
+            {
+                let flag = flag.as_str();
+                let time = format_time(time);
+                rsx! {
+                    p {
+                        Link { to: Route::Record { rid }, "{rid}" }
+                        " "
+                        Link { to: Route::Problem { pid: pid.clone() }, "{pid}" }
+                        " {flag} {time}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn UserProfile(uid: Uid) -> Element {
+    let profile = use_resource(move || async move {
+        send_message::<Profile>(FrontMessage::GetUserProfile(uid)).await
+    });
+    match &*profile.read() {
+        Some(Ok(profile)) => rsx! {
+            render_profile { profile: profile.clone() }
+        },
+        Some(Err(_)) => rsx! {
+            p { "user not found" }
+        },
+        None => rsx! {
+            p { "Loading" }
+        },
+    }
+}
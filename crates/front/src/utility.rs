@@ -4,6 +4,14 @@ pub fn now() -> i64 {
     (web_sys::js_sys::Date::now() / 1000.) as i64
 }
 
+/// Shows a unix timestamp in seconds in the local time of the browser.
+pub fn format_time(time: i64) -> String {
+    use web_sys::js_sys::Date;
+    use web_sys::wasm_bindgen::JsValue;
+    let date = Date::new(&JsValue::from_f64(time as f64 * 1000.));
+    date.to_locale_string("default", &JsValue::UNDEFINED).into()
+}
+
 #[track_caller]
 pub fn storage() -> web_sys::Storage {
     web_sys::window().unwrap().local_storage().unwrap().unwrap()
@@ -1,4 +1,31 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum Command {
+    /// Connects to the server and judges, the default.
+    Serve,
+    /// Checks which limits the testbox enforces and measures the cpu speed.
+    Selftest,
+}
+
 #[tokio::main]
 async fn main() {
-    judge::main().await.unwrap();
+    let cli = Cli::parse();
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            judge::main().await.unwrap();
+        }
+        Command::Selftest => {
+            if !judge::selftest().await.unwrap() {
+                std::process::exit(1);
+            }
+        }
+    }
 }
@@ -0,0 +1,78 @@
+//! Compiled programs kept across judges, so rejudging a record skips the compiler.
+//!
+//! Entries live in `DIR/cache`, named by a hash of everything that affects the output.
+//! The modification time of an entry is bumped on every hit and the least recently
+//! used ones are evicted once the cache grows beyond `CACHE_SIZE`.
+
+use super::*;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Serializes lookups against eviction.
+static LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone)]
+pub struct Key(String);
+
+impl Key {
+    pub fn new(language: &str, flags: &[&str], source: &str) -> Self {
+        let mut hasher = Sha256::new();
+        for part in [language].iter().chain(flags).chain([&source]) {
+            // the length keeps the boundaries between parts unambiguous
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+        Key(hex::encode(hasher.finalize()))
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    DIR.get().unwrap().join("cache")
+}
+
+/// Links the cached program of `key` to `dest`, returns false on a miss.
+pub async fn fetch(key: &Key, dest: &Path) -> eyre::Result<bool> {
+    let _guard = LOCK.lock().await;
+    let path = cache_dir().join(&key.0);
+    if !fs::try_exists(&path).await? {
+        return Ok(false);
+    }
+    // a link keeps the program alive even if the entry gets evicted during the judge
+    if fs::hard_link(&path, dest).await.is_err() {
+        fs::copy(&path, dest).await?;
+    }
+    std::fs::File::open(&path)?.set_modified(std::time::SystemTime::now())?;
+    Ok(true)
+}
+
+/// Stores a compiled program, then evicts entries beyond the size bound.
+pub async fn store(key: &Key, prog: &Path) -> eyre::Result<()> {
+    let _guard = LOCK.lock().await;
+    let dir = cache_dir();
+    fs::create_dir_all(&dir).await?;
+    let temp = dir.join(format!("{}.tmp", key.0));
+    fs::copy(prog, &temp).await?;
+    fs::rename(&temp, dir.join(&key.0)).await?;
+    evict(&dir).await
+}
+
+async fn evict(dir: &Path) -> eyre::Result<()> {
+    let mut entries = Vec::new();
+    let mut read_dir = fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        entries.push((metadata.modified()?, metadata.len(), entry.path()));
+    }
+    // newest first, so the ones past the bound are the least recently used
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.0));
+
+    let mut size = 0;
+    for (_, len, path) in entries {
+        size += len;
+        if size > *CACHE_SIZE << 20 {
+            tracing::info!("evict {}", path.display());
+            fs::remove_file(path).await?;
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,19 @@
 use super::*;
+use shared::comp::first_difference;
 use shared::problem::*;
+use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
-use testbox::{PlatformTestBox, TestBox};
-use tokio::{fs, process};
+use std::time::Instant;
+use testbox::{NativeTestBox, PlatformTestBox, TestBox};
+use tokio::fs;
+
+const OUTPUT_LIMIT: u64 = 64 << 20;
+
+const CPP_FLAGS: [&str; 3] = ["-O2", "-std=c++14", "-static"];
+
+/// Bounds the size of the executable as well as the output of the compiler.
+const COMPILE_OUTPUT_LIMIT: u64 = 64 << 20;
 
 fn problem_file_path(pid: &Pid, file: &str) -> PathBuf {
     DIR.get().unwrap().join("problem").join(&pid.0).join(file)
@@ -47,91 +58,216 @@ async fn prepare(data: &ProblemData) -> eyre::Result<()> {
 }
 
 #[instrument]
-async fn compile(dir: &Path, code: &str) -> eyre::Result<PathBuf> {
+pub(crate) async fn compile(dir: &Path, code: &str) -> eyre::Result<PathBuf> {
     tracing::info!("compile");
 
-    let code_file = dir.join("prog.cpp");
-    fs::write(&code_file, code).await?;
-    let out_file = dir.join("prog");
-    let output = process::Command::new("g++")
-        .arg(&code_file)
-        .arg("-o")
-        .arg(&out_file)
-        .arg("-O2")
-        .arg("-std=c++14")
-        .arg("-static")
-        .output()
-        .await?;
-    if output.status.success() {
-        Ok(out_file)
+    let work = dir.join("work");
+    fs::create_dir(&work).await?;
+    fs::write(work.join("prog.cpp"), code).await?;
+    let time_limit = *COMPILE_TIME_LIMIT;
+    let config = testbox::Config {
+        root: dir.join("root"),
+        memory_limit: *COMPILE_MEMORY_LIMIT << 20,
+        cpu_time_limit: Duration::from_millis(time_limit),
+        wall_time_limit: Duration::from_millis(time_limit * 2),
+        idle_limit: Duration::from_millis(time_limit),
+        output_limit: COMPILE_OUTPUT_LIMIT,
+        stack_limit: *COMPILE_MEMORY_LIMIT << 20,
+        // the driver runs the compiler, assembler and linker as child processes
+        process_limit: 16,
+        open_files_limit: 256,
+        file_io: None,
+        cgroup: CGROUP.clone(),
+    };
+    let args = ["prog.cpp", "-o", "prog"]
+        .into_iter()
+        .chain(CPP_FLAGS)
+        .map(OsStr::new);
+    let run_result = if *NATIVE_SANDBOX {
+        let testbox = NativeTestBox::new(&config).await?;
+        testbox.run_command(&work, "g++", args).await?
     } else {
-        Err(CompileError {
-            exit_code: output.status.code(),
-            message: String::from_utf8(output.stderr)?,
+        let testbox = PlatformTestBox::new(&config).await?;
+        testbox.run_command(&work, "g++", args).await?
+    };
+
+    tracing::info!("compile status {:?}", run_result.status);
+    let kind = match run_result.status {
+        testbox::Status::Okay => return Ok(work.join("prog")),
+        testbox::Status::TimeLimitExceed | testbox::Status::IdleLimitExceeded => {
+            CompileErrorKind::TimeLimitExceeded
+        }
+        testbox::Status::MemoryLimitExceed => CompileErrorKind::MemoryLimitExceeded,
+        testbox::Status::RuntimeError | testbox::Status::OutputLimitExceeded => {
+            CompileErrorKind::Failed
         }
-        .into())
+    };
+    Err(CompileError {
+        exit_code: run_result.exit_code,
+        message: String::from_utf8_lossy(&run_result.stderr).into_owned(),
+        kind,
     }
+    .into())
 }
 
+/// Takes the program from the cache when the same code was compiled before.
 #[instrument]
-async fn run_testcase(
-    pid: &Pid,
+async fn compile_cached(dir: &Path, code: &str) -> eyre::Result<PathBuf> {
+    let key = cache::Key::new("cpp", &CPP_FLAGS, code);
+    let prog = dir.join("prog");
+    if cache::fetch(&key, &prog).await? {
+        tracing::info!("compile cache hit");
+        return Ok(prog);
+    }
+    let prog = compile(dir, code).await?;
+    cache::store(&key, &prog).await?;
+    Ok(prog)
+}
+
+/// Runs the program once on the limits of a problem.
+async fn run_program(
     prog: &Path,
     time_limit: u32,
     memory_limit: u32,
-    case: &Testcase,
-) -> eyre::Result<SingleJudgeResult> {
-    tracing::info!("running testcase");
-
+    run_limits: RunLimits,
+    file_io: Option<&FileIo>,
+    input: &[u8],
+) -> eyre::Result<testbox::RunResult> {
     let testbox_dir = tempfile::TempDir::new()?;
-    let testbox = PlatformTestBox::new(&testbox::Config {
+    let config = testbox::Config {
         root: testbox_dir.path().into(),
         memory_limit: (memory_limit as u64) << 20,
-        time_limit: Duration::from_millis(time_limit as u64),
-    })
-    .await?;
+        cpu_time_limit: Duration::from_millis(time_limit as u64),
+        wall_time_limit: Duration::from_millis(time_limit as u64 * 2 + 1000),
+        idle_limit: Duration::from_millis(time_limit as u64 + 1000),
+        output_limit: OUTPUT_LIMIT,
+        stack_limit: (run_limits.stack.unwrap_or(memory_limit) as u64) << 20,
+        process_limit: run_limits.processes,
+        open_files_limit: run_limits.open_files as u64,
+        file_io: file_io.map(|file_io| testbox::FileIo {
+            input: file_io.input.to_string(),
+            output: file_io.output.to_string(),
+        }),
+        cgroup: CGROUP.clone(),
+    };
 
-    let input = fs::read(problem_file_path(pid, &case.input_file)).await?;
-    let run_result = testbox.run_single(prog, None, &input).await?;
+    let run_result = if *NATIVE_SANDBOX {
+        let testbox = NativeTestBox::new(&config).await?;
+        testbox.run_single(prog, None, input).await?
+    } else {
+        let testbox = PlatformTestBox::new(&config).await?;
+        testbox.run_single(prog, None, input).await?
+    };
 
     tracing::info!("run status {:?}", run_result.status);
+    if !run_result.stderr.is_empty() {
+        tracing::debug!("stderr {}", String::from_utf8_lossy(&run_result.stderr));
+    }
+    Ok(run_result)
+}
 
+/// The result of a run before looking at its output, `Ac` if it exited normally.
+fn run_verdict(run_result: &testbox::RunResult) -> SingleJudgeResult {
     let mut ret = SingleJudgeResult {
         memory_used: (run_result.memory_used >> 20) as u32,
-        time_used: run_result.time_used.as_millis() as u32,
+        time_used: run_result.cpu_time.as_millis() as u32,
+        wall_time: run_result.wall_time.as_millis() as u32,
+        termination: None,
         verdict: Verdict::Ac,
     };
-    match run_result.status {
-        testbox::Status::Okay => {}
-        testbox::Status::TimeLimitExceed => {
-            ret.verdict = Verdict::Tle;
-            return Ok(ret);
-        }
-        testbox::Status::MemoryLimitExceed => {
-            ret.verdict = Verdict::Mle;
-            return Ok(ret);
-        }
+    ret.verdict = match run_result.status {
+        testbox::Status::Okay => Verdict::Ac,
+        testbox::Status::TimeLimitExceed | testbox::Status::IdleLimitExceeded => Verdict::Tle,
+        testbox::Status::MemoryLimitExceed => Verdict::Mle,
+        testbox::Status::OutputLimitExceeded => Verdict::Ole,
         testbox::Status::RuntimeError => {
-            ret.verdict = Verdict::Re;
-            return Ok(ret);
+            ret.termination = match (run_result.signal, run_result.exit_code) {
+                (Some(signal), _) => Some(Termination::Signal(signal)),
+                (None, Some(code)) => Some(Termination::ExitCode(code)),
+                (None, None) => None,
+            };
+            Verdict::Re
         }
+    };
+    ret
+}
+
+#[instrument]
+async fn run_testcase(
+    pid: &Pid,
+    prog: &Path,
+    time_limit: u32,
+    memory_limit: u32,
+    run_limits: RunLimits,
+    file_io: Option<&FileIo>,
+    case: &Testcase,
+) -> eyre::Result<(SingleJudgeResult, Vec<u8>)> {
+    tracing::info!("running testcase");
+
+    let input = fs::read(problem_file_path(pid, &case.input_file)).await?;
+    let run_result =
+        run_program(prog, time_limit, memory_limit, run_limits, file_io, &input).await?;
+    let mut ret = run_verdict(&run_result);
+    let output = match file_io {
+        Some(_) => run_result.output_file,
+        None => Some(run_result.stdout),
+    };
+    if ret.verdict != Verdict::Ac {
+        return Ok((ret, output.unwrap_or_default()));
     }
+    let Some(output) = output else {
+        ret.verdict = Verdict::Wa;
+        return Ok((ret, Vec::new()));
+    };
 
-    let stdout = match String::from_utf8(run_result.stdout) {
-        Ok(c) => c,
-        Err(_) => {
-            ret.verdict = Verdict::Wa;
-            return Ok(ret);
+    ret.verdict = match std::str::from_utf8(&output) {
+        Ok(stdout) => {
+            let answer = fs::read_to_string(problem_file_path(pid, &case.output_file)).await?;
+            if first_difference(stdout, &answer).is_none() {
+                Verdict::Ac
+            } else {
+                Verdict::Wa
+            }
         }
+        Err(_) => Verdict::Wa,
     };
+    Ok((ret, output))
+}
 
-    let answer = fs::read_to_string(problem_file_path(pid, &case.output_file)).await?;
-    ret.verdict = if comp::comp(&answer, &stdout) {
-        Verdict::Ac
-    } else {
-        Verdict::Wa
+struct KeptOutput {
+    kept: Instant,
+    output: Vec<u8>,
+}
+
+/// Outputs of failed testcases, kept for a while in case a manager asks for them.
+static CASE_OUTPUTS: LazyLock<Mutex<HashMap<(Rid, usize), KeptOutput>>> =
+    LazyLock::new(Default::default);
+
+const CASE_OUTPUT_RETENTION: Duration = Duration::from_secs(60 * 60);
+const MAX_CASE_OUTPUTS: usize = 4096;
+
+fn keep_case_output(rid: Rid, idx: usize, mut output: Vec<u8>) {
+    output.truncate(CASE_OUTPUT_LIMIT);
+    output.shrink_to_fit();
+    let mut outputs = CASE_OUTPUTS.lock().unwrap();
+    outputs.retain(|_, x| x.kept.elapsed() < CASE_OUTPUT_RETENTION);
+    if outputs.len() >= MAX_CASE_OUTPUTS
+        && let Some(oldest) = outputs.iter().min_by_key(|x| x.1.kept).map(|x| *x.0)
+    {
+        outputs.remove(&oldest);
+    }
+    let kept = KeptOutput {
+        kept: Instant::now(),
+        output,
     };
-    Ok(ret)
+    outputs.insert((rid, idx), kept);
+}
+
+pub(crate) fn case_output(rid: Rid, idx: usize) -> Option<String> {
+    let outputs = CASE_OUTPUTS.lock().unwrap();
+    let x = outputs.get(&(rid, idx))?;
+    (x.kept.elapsed() < CASE_OUTPUT_RETENTION)
+        .then(|| String::from_utf8_lossy(&x.output).into_owned())
 }
 
 #[instrument]
@@ -154,10 +290,36 @@ pub async fn run_all_cases(
         let time_limit = problem_data.time_limit;
         let memory_limit = problem_data.memory_limit;
         let pid = problem_data.pid.clone();
+        let run_limits = problem_data.run_limits;
+        let file_io = problem_data.file_io.clone();
         let handle = tokio::spawn(async move {
-            let res = run_testcase(&pid, &prog, time_limit, memory_limit, &case).await?;
-            let _: () =
-                send_message(JudgeMessage::SendSingleJudgeResult(rid, idx, res.clone())).await?;
+            let (res, output) = run_testcase(
+                &pid,
+                &prog,
+                time_limit,
+                memory_limit,
+                run_limits,
+                file_io.as_ref(),
+                &case,
+            )
+            .await?;
+            let sample_output = if case.is_sample {
+                let end = output.len().min(SAMPLE_LIMIT);
+                Some(String::from_utf8_lossy(&output[..end]).into_owned())
+            } else {
+                None
+            };
+            if !case.is_sample && res.verdict != Verdict::Ac {
+                keep_case_output(rid, idx, output);
+            }
+            report(JudgeMessage::SendSingleJudgeResult(
+                *UUID,
+                rid,
+                idx,
+                res.clone(),
+                sample_output,
+            ))
+            .await?;
             Ok::<_, eyre::Report>(res)
         });
         cases.push(handle);
@@ -213,12 +375,13 @@ pub async fn judge(rid: Rid) -> eyre::Result<()> {
     prepare(&problem_data).await?;
 
     let compile_dir = tempfile::TempDir::new()?;
-    let prog = match compile(compile_dir.path(), &code).await {
+    let prog = match compile_cached(compile_dir.path(), &code).await {
         Ok(path) => path,
         Err(err) => {
             if let Some(ce) = err.downcast_ref::<CompileError>() {
                 tracing::info!("compile error {:#?}", ce);
-                let _: () = send_message(JudgeMessage::SendCompileResult(
+                report(JudgeMessage::SendCompileResult(
+                    *UUID,
                     rid,
                     CompileResult::Error(ce.clone()),
                 ))
@@ -230,7 +393,8 @@ pub async fn judge(rid: Rid) -> eyre::Result<()> {
     };
 
     tracing::info!("compiled");
-    let _: () = send_message(JudgeMessage::SendCompileResult(
+    report(JudgeMessage::SendCompileResult(
+        *UUID,
         rid,
         CompileResult::Compiled,
     ))
@@ -238,7 +402,54 @@ pub async fn judge(rid: Rid) -> eyre::Result<()> {
 
     let res = run_all_cases(rid, &prog, &problem_data).await?;
 
-    let _: () = send_message(JudgeMessage::SendAllJudgeResults(rid, res)).await?;
+    report(JudgeMessage::SendAllJudgeResults(*UUID, rid, res)).await?;
+
+    Ok(())
+}
+
+/// Truncates to at most `CUSTOM_RUN_OUTPUT_LIMIT` bytes for showing to the user.
+fn output_prefix(mut data: Vec<u8>) -> String {
+    data.truncate(CUSTOM_RUN_OUTPUT_LIMIT);
+    String::from_utf8_lossy(&data).into_owned()
+}
+
+#[instrument]
+pub async fn custom_run(id: RunId) -> eyre::Result<()> {
+    let CustomRun { pid, code, stdin } = send_message(JudgeMessage::GetCustomRun(id)).await?;
+    let problem_data: ProblemData = send_message(JudgeMessage::GetProblemData(pid)).await?;
 
+    let compile_dir = tempfile::TempDir::new()?;
+    let prog = match compile_cached(compile_dir.path(), &code).await {
+        Ok(path) => path,
+        Err(err) => {
+            if let Some(ce) = err.downcast_ref::<CompileError>() {
+                let status = CustomRunStatus::CompileError(ce.clone());
+                report(JudgeMessage::SendCustomRunResult(*UUID, id, status)).await?;
+                return Ok(());
+            }
+            return Err(err);
+        }
+    };
+
+    let run_result = run_program(
+        &prog,
+        problem_data.time_limit,
+        problem_data.memory_limit,
+        problem_data.run_limits,
+        problem_data.file_io.as_ref(),
+        stdin.as_bytes(),
+    )
+    .await?;
+    let result = run_verdict(&run_result);
+    let output = match problem_data.file_io {
+        Some(_) => run_result.output_file.unwrap_or_default(),
+        None => run_result.stdout,
+    };
+    let status = CustomRunStatus::Finished(CustomRunOutput {
+        result,
+        stdout: output_prefix(output),
+        stderr: output_prefix(run_result.stderr),
+    });
+    report(JudgeMessage::SendCustomRunResult(*UUID, id, status)).await?;
     Ok(())
 }
@@ -1,20 +1,57 @@
-mod comp;
+mod cache;
 mod judge;
+mod selftest;
+mod ws;
 
 use serde::de::DeserializeOwned;
 use shared::judge::*;
 use shared::record::*;
+use std::collections::HashSet;
 use std::path::PathBuf;
-use std::sync::{LazyLock, OnceLock};
+use std::sync::{LazyLock, Mutex, OnceLock};
 use std::time::Duration;
 use tokio::fs;
 use tracing::instrument;
+
+pub use selftest::selftest;
 use uuid::Uuid;
 
 static DIR: OnceLock<PathBuf> = OnceLock::new();
 
 static UUID: LazyLock<Uuid> = LazyLock::new(Uuid::new_v4);
 
+/// Parent cgroup for the testbox, taken from `MYGOJ_CGROUP`.
+static CGROUP: LazyLock<Option<PathBuf>> =
+    LazyLock::new(|| std::env::var_os("MYGOJ_CGROUP").map(PathBuf::from));
+
+/// Runs programs in the built-in namespace sandbox instead of firejail when `MYGOJ_SANDBOX=native`.
+static NATIVE_SANDBOX: LazyLock<bool> =
+    LazyLock::new(|| std::env::var_os("MYGOJ_SANDBOX").is_some_and(|v| v == "native"));
+
+/// Cpu time limit of compiling in ms, taken from `MYGOJ_COMPILE_TIME_LIMIT`.
+static COMPILE_TIME_LIMIT: LazyLock<u64> =
+    LazyLock::new(|| env_number("MYGOJ_COMPILE_TIME_LIMIT").unwrap_or(10_000));
+
+/// Memory limit of compiling in MiB, taken from `MYGOJ_COMPILE_MEMORY_LIMIT`.
+static COMPILE_MEMORY_LIMIT: LazyLock<u64> =
+    LazyLock::new(|| env_number("MYGOJ_COMPILE_MEMORY_LIMIT").unwrap_or(1024));
+
+/// Upper bound of the compile cache in MiB, taken from `MYGOJ_CACHE_SIZE`.
+static CACHE_SIZE: LazyLock<u64> = LazyLock::new(|| env_number("MYGOJ_CACHE_SIZE").unwrap_or(1024));
+
+fn env_number(key: &str) -> Option<u64> {
+    let value = std::env::var(key).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            tracing::warn!("ignore invalid {key}={value}");
+            None
+        }
+    }
+}
+
+static TASKS: LazyLock<Mutex<HashSet<JudgeTask>>> = LazyLock::new(Default::default);
+
 const SERVER_ORIGN: &str = "http://localhost:5800";
 
 async fn send_message<T>(msg: JudgeMessage) -> eyre::Result<T>
@@ -44,42 +81,124 @@ async fn get_bin(msg: JudgeMessage) -> eyre::Result<Vec<u8>> {
     Ok(Vec::from(res))
 }
 
+/// Reports a result through the websocket if it is connected, or over http otherwise.
+async fn report(msg: JudgeMessage) -> eyre::Result<()> {
+    let msg = match ws::sender() {
+        Some(sender) => match sender.send(msg) {
+            Ok(()) => return Ok(()),
+            Err(err) => err.0,
+        },
+        None => msg,
+    };
+    send_message(msg).await
+}
+
 async fn execute(command: JudgeCommand) -> eyre::Result<()> {
     match command {
-        JudgeCommand::Judge(rid) => judge::judge(rid).await.unwrap(),
+        JudgeCommand::Judge(rid) => {
+            TASKS.lock().unwrap().insert(JudgeTask::Record(rid));
+            let ret = judge::judge(rid).await;
+            TASKS.lock().unwrap().remove(&JudgeTask::Record(rid));
+            if let Err(err) = ret {
+                tracing::error!("failed to judge {rid} {err:?}");
+            }
+        }
+        JudgeCommand::Run(id) => {
+            TASKS.lock().unwrap().insert(JudgeTask::Run(id));
+            let ret = judge::custom_run(id).await;
+            TASKS.lock().unwrap().remove(&JudgeTask::Run(id));
+            if let Err(err) = ret {
+                tracing::error!("failed to run {id} {err:?}");
+            }
+        }
+        JudgeCommand::UploadCaseOutput(rid, idx) => {
+            let output = judge::case_output(rid, idx);
+            report(JudgeMessage::SendCaseOutput(*UUID, rid, idx, output)).await?;
+        }
         JudgeCommand::Null => {}
     }
     Ok(())
 }
 
 use reqwest::Client;
-async fn connect() {
-    let mut system = sysinfo::System::new_all();
-    let cpus = system.cpus();
-    dbg!(&cpus);
-    let cpu_name = cpus.iter().fold(String::new(), |mut a, b| {
-        a.push_str(&format!("[{}] {} MHz ", b.name(), b.frequency()));
-        a
-    });
-    let system_name = sysinfo::System::name();
-    let hostname = sysinfo::System::host_name();
-    loop {
-        system.refresh_all();
-        let cpu_usage = system.global_cpu_usage() as u32;
-        let tasks = Vec::new();
-        let signal = JudgeMachineSignal {
-            cpu_name: cpu_name.clone(),
+
+struct Machine {
+    system: sysinfo::System,
+    cpu_name: String,
+    system_name: Option<String>,
+    hostname: Option<String>,
+    capacity: u32,
+    cpu_benchmark: u32,
+}
+
+impl Machine {
+    fn new() -> Self {
+        let system = sysinfo::System::new_all();
+        let cpus = system.cpus();
+        dbg!(&cpus);
+        let cpu_name = cpus.iter().fold(String::new(), |mut a, b| {
+            a.push_str(&format!("[{}] {} MHz ", b.name(), b.frequency()));
+            a
+        });
+        let capacity = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+        Machine {
+            system,
+            cpu_name,
+            system_name: sysinfo::System::name(),
+            hostname: sysinfo::System::host_name(),
+            capacity,
+            cpu_benchmark: selftest::benchmark(),
+        }
+    }
+
+    fn signal(&mut self) -> JudgeMachineSignal {
+        self.system.refresh_all();
+        let cpu_usage = self.system.global_cpu_usage() as u32;
+        let (mut tasks, mut runs) = (Vec::new(), Vec::new());
+        for task in TASKS.lock().unwrap().iter() {
+            match *task {
+                JudgeTask::Record(rid) => tasks.push(rid),
+                JudgeTask::Run(id) => runs.push(id),
+            }
+        }
+        JudgeMachineSignal {
+            cpu_name: self.cpu_name.clone(),
             cpu_usage,
-            system_name: system_name.clone(),
-            hostname: hostname.clone(),
+            system_name: self.system_name.clone(),
+            hostname: self.hostname.clone(),
             tasks,
+            runs,
+            capacity: self.capacity,
+            cpu_benchmark: self.cpu_benchmark,
             uuid: *UUID,
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
-        };
-        let command: JudgeCommand = send_message(JudgeMessage::Signal(signal)).await.unwrap();
+        }
+    }
+}
+
+async fn poll(machine: &mut Machine, rounds: usize) -> eyre::Result<()> {
+    for _ in 0..rounds {
+        let command: JudgeCommand = send_message(JudgeMessage::Signal(machine.signal())).await?;
         tokio::spawn(execute(command));
         tokio::time::sleep(Duration::from_millis(1000)).await;
     }
+    Ok(())
+}
+
+async fn connect() {
+    let mut machine = tokio::task::spawn_blocking(Machine::new).await.unwrap();
+    loop {
+        match ws::session(&mut machine).await {
+            Ok(()) => tracing::info!("websocket closed, reconnect"),
+            Err(err) => {
+                tracing::warn!("websocket unavailable, fall back to polling {err:?}");
+                if let Err(err) = poll(&mut machine, 10).await {
+                    tracing::error!("fail to poll server {err:?}");
+                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                }
+            }
+        }
+    }
 }
 
 pub async fn main() -> eyre::Result<()> {
@@ -0,0 +1,203 @@
+//! Runs programs breaking each limit through the testbox and reports which limits hold,
+//! so a misconfigured sandbox is noticed before it judges anything.
+
+use super::*;
+use std::path::Path;
+use std::time::Instant;
+use testbox::{NativeTestBox, PlatformTestBox, Status, TestBox};
+
+/// Stack limit of the runs, small enough that overflowing it stays within the memory limit.
+const STACK_LIMIT: u64 = 64 << 20;
+
+/// Every program prints `escaped` once it got away with what it tried,
+/// and gets `STACK_LIMIT` on stdin.
+struct Case {
+    name: &'static str,
+    code: &'static str,
+    /// What the testbox may report when the limit holds.
+    expected: &'static [Status],
+}
+
+const CASES: [Case; 6] = [
+    Case {
+        name: "infinite loop",
+        code: r#"
+#include <cstdio>
+int main() {
+    volatile unsigned long long x = 0;
+    for (unsigned long long i = 0; i < 100000000000ull; i++) x += i;
+    puts("escaped");
+}
+"#,
+        expected: &[Status::TimeLimitExceed],
+    },
+    Case {
+        name: "memory bomb",
+        code: r#"
+#include <cstdio>
+#include <cstring>
+int main() {
+    for (int i = 0; i < 64; i++) {
+        char *p = new char[64 << 20];
+        memset(p, 1, 64 << 20);
+    }
+    puts("escaped");
+}
+"#,
+        expected: &[Status::MemoryLimitExceed],
+    },
+    Case {
+        name: "fork bomb",
+        code: r#"
+#include <cstdio>
+#include <unistd.h>
+int main() {
+    int forked = 0;
+    for (int i = 0; i < 64; i++) {
+        pid_t pid = fork();
+        if (pid == 0) {
+            sleep(1);
+            _exit(0);
+        }
+        if (pid > 0) forked++;
+    }
+    if (forked >= 32) puts("escaped");
+}
+"#,
+        expected: &[Status::Okay],
+    },
+    Case {
+        name: "network access",
+        code: r#"
+#include <arpa/inet.h>
+#include <cstdio>
+#include <sys/socket.h>
+int main() {
+    int fd = socket(AF_INET, SOCK_DGRAM, 0);
+    sockaddr_in addr{};
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(53);
+    inet_pton(AF_INET, "1.1.1.1", &addr.sin_addr);
+    if (fd >= 0 && sendto(fd, "x", 1, 0, (sockaddr *)&addr, sizeof(addr)) == 1) puts("escaped");
+}
+"#,
+        // the native sandbox kills it for calling socket
+        expected: &[Status::Okay, Status::RuntimeError],
+    },
+    Case {
+        name: "writing outside root",
+        code: r#"
+#include <cstdio>
+int main(int argc, char **argv) {
+    FILE *f = fopen(argv[1], "w");
+    if (f && fputs("x", f) >= 0 && fclose(f) == 0) puts("written");
+}
+"#,
+        expected: &[Status::Okay],
+    },
+    Case {
+        name: "stack overflow",
+        code: r#"
+#include <cstdio>
+volatile char sink;
+void dive(long long depth) {
+    volatile char frame[4096];
+    frame[0] = (char)depth;
+    if (depth > 0) dive(depth - 1);
+    sink = frame[0];
+}
+int main() {
+    long long limit;
+    scanf("%lld", &limit);
+    // a megabyte past the limit, far below the memory limit
+    dive(limit / 4096 + 256);
+    puts("escaped");
+}
+"#,
+        expected: &[Status::RuntimeError],
+    },
+];
+
+/// Runs a fixed single-threaded workload for a moment, the score is rounds per second.
+pub fn benchmark() -> u32 {
+    let mut table = vec![0u32; 1 << 20];
+    let mut x = 0x9e37_79b9_7f4a_7c15u64;
+    let mut rounds = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) {
+        for _ in 0..1000 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            let slot = &mut table[(x as usize) & ((1 << 20) - 1)];
+            *slot = slot.wrapping_add(x as u32);
+        }
+        rounds += 1;
+    }
+    std::hint::black_box(&table);
+    (rounds as f64 / start.elapsed().as_secs_f64()) as u32
+}
+
+async fn run_case(dir: &Path, case: &Case, outside: &Path) -> eyre::Result<(Status, bool)> {
+    let prog = judge::compile(dir, case.code).await?;
+    let config = testbox::Config {
+        root: dir.join("root"),
+        memory_limit: 256 << 20,
+        cpu_time_limit: Duration::from_millis(1000),
+        wall_time_limit: Duration::from_millis(3000),
+        idle_limit: Duration::from_millis(2000),
+        output_limit: 1 << 20,
+        stack_limit: STACK_LIMIT,
+        process_limit: 1,
+        open_files_limit: 64,
+        file_io: None,
+        cgroup: CGROUP.clone(),
+    };
+    let args = [outside.as_os_str()];
+    let stdin = STACK_LIMIT.to_string();
+    let res = if *NATIVE_SANDBOX {
+        let testbox = NativeTestBox::new(&config).await?;
+        testbox.run_single(&prog, args, &stdin).await?
+    } else {
+        let testbox = PlatformTestBox::new(&config).await?;
+        testbox.run_single(&prog, args, &stdin).await?
+    };
+    let escaped =
+        String::from_utf8_lossy(&res.stdout).contains("escaped") || fs::try_exists(outside).await?;
+    Ok((res.status, !escaped && case.expected.contains(&res.status)))
+}
+
+/// Prints a report and tells whether every limit is enforced.
+pub async fn selftest() -> eyre::Result<bool> {
+    let sandbox = if *NATIVE_SANDBOX {
+        "native"
+    } else {
+        "firejail"
+    };
+    println!("sandbox {sandbox}, cgroup {:?}", *CGROUP);
+
+    let outside = std::env::temp_dir().join(format!("mygoj-selftest-{}", *UUID));
+    let mut all = true;
+    for case in &CASES {
+        let dir = tempfile::TempDir::new()?;
+        let ret = run_case(dir.path(), case, &outside).await;
+        if fs::try_exists(&outside).await? {
+            fs::remove_file(&outside).await?;
+        }
+        match ret {
+            Ok((status, enforced)) => {
+                let verdict = if enforced { "enforced" } else { "NOT ENFORCED" };
+                println!("{:<24}{verdict:<16}{status:?}", case.name);
+                all &= enforced;
+            }
+            Err(err) => {
+                println!("{:<24}{:<16}{err}", case.name, "FAILED");
+                all = false;
+            }
+        }
+    }
+
+    let score = tokio::task::spawn_blocking(benchmark).await?;
+    println!("cpu benchmark {score}");
+    Ok(all)
+}
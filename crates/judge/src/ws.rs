@@ -0,0 +1,53 @@
+use super::*;
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+static SENDER: Mutex<Option<mpsc::UnboundedSender<JudgeMessage>>> = Mutex::new(None);
+
+pub fn sender() -> Option<mpsc::UnboundedSender<JudgeMessage>> {
+    SENDER.lock().unwrap().clone()
+}
+
+/// Keeps a websocket to the server open, sending signals and results through it
+/// and executing the commands the server pushes back.
+pub async fn session(machine: &mut Machine) -> eyre::Result<()> {
+    let url = format!("{}/api/judge_ws", SERVER_ORIGN.replacen("http", "ws", 1));
+    let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+    tracing::info!("websocket connected");
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    *SENDER.lock().unwrap() = Some(tx);
+
+    let mut tick = tokio::time::interval(Duration::from_millis(1000));
+    let ret = async {
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {
+                    let signal = JudgeMessage::Signal(machine.signal());
+                    sink.send(Message::text(serde_json::to_string(&signal)?)).await?;
+                }
+                Some(msg) = rx.recv() => {
+                    sink.send(Message::text(serde_json::to_string(&msg)?)).await?;
+                }
+                msg = stream.next() => {
+                    let Some(msg) = msg else {
+                        return Ok(());
+                    };
+                    if let Message::Text(text) = msg? {
+                        let command: JudgeCommand = serde_json::from_str(&text)?;
+                        tracing::info!("receive command {command:?}");
+                        tokio::spawn(execute(command));
+                    }
+                }
+            }
+        }
+    }
+    .await;
+
+    *SENDER.lock().unwrap() = None;
+    while let Ok(msg) = rx.try_recv() {
+        send_message::<()>(msg).await?;
+    }
+    ret
+}
@@ -0,0 +1,260 @@
+//! Backups of the whole storage as a tar archive, holding a snapshot of the
+//! database as `data.db` and every problem file it refers to under `problems/`.
+//! They can be taken while the server runs, from the server or the command line.
+
+use super::*;
+use crate::problem::{Problem, problem_files_lock, problems_storage_path};
+use libsqlite3_sys as ffi;
+use sqlx::{Connection, SqliteConnection};
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::fs;
+
+const DATABASE: &str = "data.db";
+const PROBLEMS: &str = "problems";
+
+#[derive(Debug)]
+pub struct BackupSummary {
+    pub problems: usize,
+    pub files: usize,
+}
+
+async fn snapshot_problems(path: &Path) -> eyre::Result<Vec<Problem>> {
+    let mut con = SqliteConnection::connect(path.to_str().unwrap()).await?;
+    let problems = sqlx::query_as("SELECT * FROM problems")
+        .fetch_all(&mut con)
+        .await?;
+    con.close().await?;
+    Ok(problems)
+}
+
+/// A database opened through the C api of sqlite, closed on drop.
+struct RawDatabase(*mut ffi::sqlite3);
+
+impl RawDatabase {
+    fn open(path: &Path, flags: i32) -> eyre::Result<Self> {
+        let name = CString::new(path.as_os_str().as_bytes())?;
+        let mut handle = std::ptr::null_mut();
+        // SAFETY: the name is a valid C string, the handle is closed by drop
+        let code =
+            unsafe { ffi::sqlite3_open_v2(name.as_ptr(), &mut handle, flags, std::ptr::null()) };
+        // sqlite hands out a handle to close even when opening fails
+        let database = RawDatabase(handle);
+        if code != ffi::SQLITE_OK {
+            eyre::bail!("fail to open {}: {}", path.display(), error_message(code));
+        }
+        Ok(database)
+    }
+}
+
+impl Drop for RawDatabase {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from sqlite3_open_v2 and is not used afterwards
+        unsafe { ffi::sqlite3_close(self.0) };
+    }
+}
+
+fn error_message(code: i32) -> String {
+    // SAFETY: sqlite3_errstr returns a static string for every code
+    unsafe { CStr::from_ptr(ffi::sqlite3_errstr(code)) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Copies the database with the online backup api of sqlite. All pages are copied
+/// in a single step under a read lock, so the copy is consistent while others write.
+fn snapshot_database(source: &Path, target: &Path) -> eyre::Result<()> {
+    let source = RawDatabase::open(source, ffi::SQLITE_OPEN_READWRITE)?;
+    let target = RawDatabase::open(target, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE)?;
+    let main = c"main";
+    // SAFETY: both handles are open until the backup is finished
+    let code = unsafe {
+        ffi::sqlite3_busy_timeout(source.0, 5000);
+        let backup = ffi::sqlite3_backup_init(target.0, main.as_ptr(), source.0, main.as_ptr());
+        if backup.is_null() {
+            ffi::sqlite3_errcode(target.0)
+        } else {
+            let step = ffi::sqlite3_backup_step(backup, -1);
+            let finish = ffi::sqlite3_backup_finish(backup);
+            if step == ffi::SQLITE_DONE {
+                finish
+            } else {
+                step
+            }
+        }
+    };
+    if code != ffi::SQLITE_OK {
+        eyre::bail!("fail to back up the database: {}", error_message(code));
+    }
+    Ok(())
+}
+
+/// Writes a backup of the storage to `archive`.
+pub async fn backup(archive: &Path) -> eyre::Result<BackupSummary> {
+    let database = storage_dir().join(DATABASE);
+    if !fs::try_exists(&database).await? {
+        eyre::bail!("no database at {}, run init first", database.display());
+    }
+    // no problem file is removed from before the snapshot until it is copied
+    let files_lock = problem_files_lock(true).await?;
+    let staging = tempfile::tempdir()?;
+    let snapshot = staging.path().join(DATABASE);
+    let target = snapshot.clone();
+    tokio::task::spawn_blocking(move || snapshot_database(&database, &target)).await??;
+    let problems = snapshot_problems(&snapshot).await?;
+
+    let root = problems_storage_path();
+    let mut files = Vec::new();
+    for problem in &problems {
+        for file in problem.files.iter() {
+            let name = Path::new(&problem.pid.0).join(file.uuid.to_string());
+            files.push(name);
+        }
+    }
+    let summary = BackupSummary {
+        problems: problems.len(),
+        files: files.len(),
+    };
+
+    let dir = archive.parent().unwrap_or(Path::new("."));
+    let out = tempfile::NamedTempFile::new_in(dir)?;
+    let archive = archive.to_owned();
+    tokio::task::spawn_blocking(move || -> eyre::Result<()> {
+        let mut builder = tar::Builder::new(out.as_file());
+        builder.append_path_with_name(&snapshot, DATABASE)?;
+        for name in files {
+            let path = root.join(&name);
+            builder
+                .append_path_with_name(&path, Path::new(PROBLEMS).join(&name))
+                .map_err(|err| eyre::eyre!("fail to add {}: {err}", path.display()))?;
+        }
+        builder.finish()?;
+        drop(builder);
+        out.as_file().sync_all()?;
+        out.persist(&archive)?;
+        Ok(())
+    })
+    .await??;
+    drop(files_lock);
+    Ok(summary)
+}
+
+static BACKING_UP: AtomicBool = AtomicBool::new(false);
+
+/// Starts a backup into `backups` of the storage directory and returns the name
+/// of the archive, which shows up there once the backup is done.
+pub async fn start_backup() -> Result<String, ServerError> {
+    if BACKING_UP.swap(true, Ordering::AcqRel) {
+        return Err(ServerError::Fuck);
+    }
+    let dir = storage_dir().join("backups");
+    if let Err(err) = fs::create_dir_all(&dir).await {
+        BACKING_UP.store(false, Ordering::Release);
+        return Err(ServerError::into_internal(err));
+    }
+    let name = format!("backup-{}.tar", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+    let archive = dir.join(&name);
+    tokio::spawn(async move {
+        match backup(&archive).await {
+            Ok(summary) => tracing::info!("backup {} done, {summary:?}", archive.display()),
+            Err(err) => tracing::error!("backup {} failed: {err:?}", archive.display()),
+        }
+        BACKING_UP.store(false, Ordering::Release);
+    });
+    Ok(name)
+}
+
+/// Only the database and files of problems may be in an archive.
+fn valid_entry(path: &Path) -> bool {
+    let parts: Vec<_> = path.components().collect();
+    if !parts
+        .iter()
+        .all(|part| matches!(part, Component::Normal(_)))
+    {
+        return false;
+    }
+    match parts.as_slice() {
+        [name] => name.as_os_str() == DATABASE,
+        [dir, _, _] => dir.as_os_str() == PROBLEMS,
+        _ => false,
+    }
+}
+
+async fn verify(dir: &Path) -> eyre::Result<BackupSummary> {
+    let database = dir.join(DATABASE);
+    if !fs::try_exists(&database).await? {
+        eyre::bail!("the archive has no {DATABASE}");
+    }
+    let mut con = SqliteConnection::connect(database.to_str().unwrap()).await?;
+    let (integrity,): (String,) = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_one(&mut con)
+        .await?;
+    if integrity != "ok" {
+        eyre::bail!("the database is corrupted: {integrity}");
+    }
+    migrate::check_version(&mut con).await?;
+    con.close().await?;
+
+    let problems = snapshot_problems(&database).await?;
+    let mut files = 0;
+    for problem in &problems {
+        let storage = dir.join(PROBLEMS).join(&problem.pid.0);
+        let mut seen = HashSet::new();
+        for file in problem.files.iter() {
+            let path = storage.join(file.uuid.to_string());
+            let size = fs::metadata(&path).await.map(|meta| meta.len()).ok();
+            if size != Some(file.size) {
+                eyre::bail!(
+                    "file {} of {} is missing or damaged",
+                    file.path,
+                    problem.pid
+                );
+            }
+            if seen.insert(file.uuid) {
+                files += 1;
+            }
+        }
+    }
+    Ok(BackupSummary {
+        problems: problems.len(),
+        files,
+    })
+}
+
+/// Restores a backup into `storage`, which must be empty or not exist yet.
+/// Nothing is put in place unless the whole archive checks out.
+pub async fn restore(archive: &Path, storage: &Path) -> eyre::Result<BackupSummary> {
+    if fs::try_exists(storage).await? {
+        if fs::read_dir(storage).await?.next_entry().await?.is_some() {
+            eyre::bail!("{} is not empty", storage.display());
+        }
+        fs::remove_dir(storage).await?;
+    }
+    let parent = storage.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent).await?;
+    let staging = tempfile::tempdir_in(parent)?;
+
+    let file = std::fs::File::open(archive)?;
+    let target = staging.path().to_owned();
+    tokio::task::spawn_blocking(move || -> eyre::Result<()> {
+        let mut archive = tar::Archive::new(file);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            if !entry.header().entry_type().is_file() || !valid_entry(&path) {
+                eyre::bail!("unexpected entry {} in the archive", path.display());
+            }
+            entry.unpack_in(&target)?;
+        }
+        Ok(())
+    })
+    .await??;
+
+    let summary = verify(staging.path()).await?;
+    fs::create_dir_all(staging.path().join(PROBLEMS)).await?;
+    fs::rename(staging.keep(), storage).await?;
+    Ok(summary)
+}
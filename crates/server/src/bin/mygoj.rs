@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 use server::*;
+use shared::user::UserRegistration;
+use std::path::PathBuf;
 use tokio::net::TcpListener;
 
 #[derive(Debug, Parser)]
@@ -13,13 +15,15 @@ struct Cli {
 struct Serve {}
 
 impl Serve {
-    async fn serve(&self) {
+    async fn serve(&self) -> eyre::Result<()> {
         tracing::info!("starting...");
+        let _lock = lock_storage()?;
         serve::startup().await;
         let app = serve::router();
-        let listener = TcpListener::bind("0.0.0.0:5800").await.unwrap();
+        let listener = TcpListener::bind("0.0.0.0:5800").await?;
         tracing::info!("running at 5800..");
-        axum::serve(listener, app).await.unwrap();
+        axum::serve(listener, app).await?;
+        Ok(())
     }
 }
 
@@ -27,13 +31,144 @@ impl Serve {
 struct Init {}
 
 impl Init {
-    async fn init(&self) {
-        init::init_fs(storage_dir()).await.unwrap();
+    async fn init(&self) -> eyre::Result<()> {
+        // debug builds wipe an existing storage, never under a running server
+        let _lock = if storage_dir().try_exists()? {
+            Some(lock_storage()?)
+        } else {
+            None
+        };
+        init::init_fs(storage_dir()).await?;
+        let _lock = lock_storage()?;
         let db = storage_dir().join("data.db");
-        init::init_db(&db).await.unwrap();
-        init::init_problems(storage_dir().join("problems"))
-            .await
-            .unwrap();
+        init::init_db(&db).await?;
+        init::init_problems(storage_dir().join("problems")).await?;
+        Ok(())
+    }
+}
+
+/// Registers a user.
+#[derive(Parser, Debug, Clone)]
+struct AddUser {
+    #[arg(long)]
+    username: String,
+    #[arg(long)]
+    email: String,
+    #[arg(long)]
+    password: String,
+    /// Defaults to the username.
+    #[arg(long)]
+    nickname: Option<String>,
+    /// Also make the user an admin.
+    #[arg(long)]
+    admin: bool,
+}
+
+impl AddUser {
+    async fn run(self) -> eyre::Result<()> {
+        let reg = UserRegistration {
+            nickname: self
+                .nickname
+                .unwrap_or_else(|| self.username.clone())
+                .into(),
+            email: self.email.into(),
+            password: self.password.into(),
+            username: self.username.into(),
+        };
+        let uid = maintenance::add_user(reg, self.admin).await?;
+        println!("registered user {uid}");
+        Ok(())
+    }
+}
+
+/// Makes a user an admin.
+#[derive(Parser, Debug, Clone)]
+struct SetSu {
+    /// Username or email.
+    user: String,
+}
+
+impl SetSu {
+    async fn run(self) -> eyre::Result<()> {
+        let uid = maintenance::set_su(&self.user).await?;
+        println!("user {uid} is an admin now");
+        Ok(())
+    }
+}
+
+/// Sets a new password for a user and logs them out everywhere.
+#[derive(Parser, Debug, Clone)]
+struct ResetPassword {
+    /// Username or email.
+    user: String,
+    password: String,
+}
+
+impl ResetPassword {
+    async fn run(self) -> eyre::Result<()> {
+        let uid = maintenance::reset_password(&self.user, &self.password).await?;
+        println!("password of user {uid} is reset");
+        Ok(())
+    }
+}
+
+/// Brings the database schema up to date.
+#[derive(Parser, Debug, Clone)]
+struct Migrate {
+    /// Only list the migrations which would be applied.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Migrate {
+    async fn run(self) -> eyre::Result<()> {
+        let migrations = if self.dry_run {
+            migrate::pending().await?
+        } else {
+            migrate::migrate().await?
+        };
+        for migration in &migrations {
+            println!("{} {}", migration.version, migration.name);
+        }
+        let verb = if self.dry_run { "pending" } else { "applied" };
+        println!("{} migrations {verb}", migrations.len());
+        Ok(())
+    }
+}
+
+/// Writes the database and problem files into a tar archive, the server may keep running.
+#[derive(Parser, Debug, Clone)]
+struct Backup {
+    archive: PathBuf,
+}
+
+impl Backup {
+    async fn run(self) -> eyre::Result<()> {
+        let summary = backup::backup(&self.archive).await?;
+        println!(
+            "backed up {} problems with {} files into {}",
+            summary.problems,
+            summary.files,
+            self.archive.display()
+        );
+        Ok(())
+    }
+}
+
+/// Restores a backup into the storage directory, which must be empty.
+#[derive(Parser, Debug, Clone)]
+struct Restore {
+    archive: PathBuf,
+}
+
+impl Restore {
+    async fn restore(self) -> eyre::Result<()> {
+        let summary = backup::restore(&self.archive, &storage_dir()).await?;
+        println!(
+            "restored {} problems with {} files",
+            summary.problems, summary.files
+        );
+        Ok(())
     }
 }
 
@@ -41,20 +176,79 @@ impl Init {
 enum Command {
     Serve(Serve),
     Init(Init),
+    Backup(Backup),
+    Restore(Restore),
+    #[command(flatten)]
+    Maintenance(Maintenance),
+}
+
+// Commands which work on the database directly, the server must not be running.
+#[derive(Debug, Subcommand, Clone)]
+enum Maintenance {
+    AddUser(AddUser),
+    SetSu(SetSu),
+    ResetPassword(ResetPassword),
+    Migrate(Migrate),
+    /// Lists every problem.
+    Problems,
+    /// Rebuilds the columns of users from their json.
+    RebuildCaches,
+    /// Checks that problem files and records agree with the database.
+    Check,
+    /// Removes files no problem refers to anymore.
+    Clean,
+}
+
+async fn maintain(command: Maintenance) -> eyre::Result<()> {
+    maintenance::connect().await?;
+    let _lock = lock_storage()?;
+    if !matches!(command, Maintenance::Migrate(_)) && !migrate::pending().await?.is_empty() {
+        eyre::bail!("the database schema is outdated, run migrate first");
+    }
+    match command {
+        Maintenance::Migrate(args) => args.run().await?,
+        Maintenance::AddUser(args) => args.run().await?,
+        Maintenance::SetSu(args) => args.run().await?,
+        Maintenance::ResetPassword(args) => args.run().await?,
+        Maintenance::Problems => {
+            for problem in maintenance::list_problems().await? {
+                println!("{}\t{}", problem.pid, problem.title);
+            }
+        }
+        Maintenance::RebuildCaches => {
+            let users = maintenance::rebuild_caches().await?;
+            println!("rebuilt {users} users");
+        }
+        Maintenance::Check => {
+            let issues = maintenance::check_storage().await?;
+            for issue in &issues {
+                println!("{issue}");
+            }
+            if !issues.is_empty() {
+                eyre::bail!("found {} issues", issues.len());
+            }
+            println!("storage is consistent");
+        }
+        Maintenance::Clean => {
+            let count = maintenance::clean_problem_files().await?;
+            println!("removed {count} files");
+        }
+    }
+    Ok(())
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("mygoj=trace,server=trace,tower_http::trace=trace")
         .init();
     let cli = Cli::parse();
     match cli.command {
-        Command::Serve(args) => {
-            args.serve().await;
-        }
-        Command::Init(args) => {
-            args.init().await;
-        }
+        Command::Serve(args) => args.serve().await?,
+        Command::Init(args) => args.init().await?,
+        Command::Backup(args) => args.run().await?,
+        Command::Restore(args) => args.restore().await?,
+        Command::Maintenance(command) => maintain(command).await?,
     }
+    Ok(())
 }
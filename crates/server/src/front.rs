@@ -1,16 +1,25 @@
-use super::judge::judge_machines;
+use super::backup::start_backup;
+use super::judge::{judge_machines, queued_tasks};
 use super::problem::{
-    can_manage_problem,
+    can_manage_problem, create_problem,
     files::{get_problem_file_meta, require_problem_file_download_token},
     get_problem, get_problem_editable, get_problem_front,
     pages::{get_page_count, get_problems_page},
 };
-use super::record::{get_record, submit};
-use super::user::{get_user_login, remove_token, user_login, user_register};
+use super::record::{
+    get_case_output, get_record, rejudge, rejudge_problem, search_records, submit,
+};
+use super::run::{get_run_status, start_run};
+use super::user::{
+    change_email, change_nickname, change_password, force_logout, get_ranklist_page,
+    get_ranklist_page_count, get_user_login, get_user_profile, grant_role, list_users,
+    remove_token, reset_password, revoke_role, user_login, user_register,
+};
 use super::{Fuck, ServerError};
 use rust_embed::RustEmbed;
 use shared::front::FrontMessage;
 use shared::problem::Pid;
+use shared::record::RECORDS_PAGE_SIZE;
 use shared::token::Token;
 use std::borrow::Cow;
 use std::sync::LazyLock;
@@ -118,7 +127,7 @@ pub async fn logout(
     Ok(())
 }
 
-use shared::user::LoginedUser;
+use shared::user::{LoginedUser, Permission, Role};
 pub async fn receive_front_message(
     Extension(logined_user): Extension<Option<LoginedUser>>,
     Json(message): Json<FrontMessage>,
@@ -130,6 +139,11 @@ pub async fn receive_front_message(
             serde_json::to_string_pretty(&val).map_err(ServerError::into_internal)?,
         )))
     }
+    let require = |permission: Permission| match &logined_user {
+        Some(user) if user.privilege.has(permission) => Ok(()),
+        Some(_) => Err(ServerError::NoPrivilege),
+        None => Err(ServerError::Fuck),
+    };
     let can_edit_problem = async |pid: &Pid| {
         if let Some(user) = &logined_user
             && can_manage_problem(user, pid).await?
@@ -160,6 +174,12 @@ pub async fn receive_front_message(
             let editable = get_problem_editable(&pid).await?;
             to_json(&editable)
         }
+        FrontMessage::CreateProblem(pid) => {
+            require(Permission::CreateProblems)?;
+            let uid = logined_user.map(|x| x.uid).fuck()?;
+            create_problem(uid, pid).await?;
+            to_json(())
+        }
         FrontMessage::GetProblemFront(pid) => {
             let front = get_problem_front(&pid).await?;
             to_json(&front)
@@ -168,8 +188,43 @@ pub async fn receive_front_message(
             let res = judge_machines().await?;
             to_json(&res)
         }
+        FrontMessage::GetJudgeQueue => {
+            require(Permission::ManageJudges)?;
+            let queue = queued_tasks().await;
+            to_json(&queue)
+        }
+        FrontMessage::Rejudge(rid) => {
+            let pid = get_record(rid).await?.pid;
+            if require(Permission::Rejudge).is_err() {
+                can_edit_problem(&pid).await?;
+            }
+            tokio::spawn(rejudge(rid)).await.unwrap()?;
+            to_json(())
+        }
+        FrontMessage::RejudgeProblem(pid) => {
+            if require(Permission::Rejudge).is_err() {
+                can_edit_problem(&pid).await?;
+            }
+            let count = tokio::spawn(async move { rejudge_problem(&pid).await })
+                .await
+                .unwrap()?;
+            to_json(count)
+        }
         FrontMessage::GetRecord(rid) => {
-            let rec = get_record(rid).await?;
+            let mut rec = get_record(rid).await?;
+            let can_view_code = match &logined_user {
+                Some(user) => {
+                    user.uid == rec.uid
+                        || user.privilege.has(Permission::ViewAllCode)
+                        || can_manage_problem(user, &rec.pid).await?
+                }
+                None => false,
+            };
+            if !can_view_code {
+                rec.code.clear();
+            }
+            // handed out one by one through GetCaseOutput
+            rec.sample_outputs.clear();
             to_json(&rec)
         }
         FrontMessage::Submit(submission) => {
@@ -177,6 +232,89 @@ pub async fn receive_front_message(
             let rid = tokio::spawn(submit(uid, submission)).await.unwrap()?;
             to_json(rid)
         }
+        FrontMessage::StartCustomRun(run) => {
+            let uid = logined_user.map(|x| x.uid).fuck()?;
+            let id = tokio::spawn(start_run(uid, run)).await.unwrap()?;
+            to_json(id)
+        }
+        FrontMessage::GetCustomRun(id) => {
+            let uid = logined_user.map(|x| x.uid).fuck()?;
+            let status = get_run_status(uid, id).await?;
+            to_json(&status)
+        }
+        FrontMessage::GetCaseOutput(rid, idx) => {
+            let user = logined_user.fuck()?;
+            let status = get_case_output(&user, rid, idx).await?;
+            to_json(&status)
+        }
+        FrontMessage::GetUserProfile(uid) => {
+            let profile = get_user_profile(uid).await?;
+            to_json(&*profile)
+        }
+        FrontMessage::GetRanklistPage(index) => {
+            let page = get_ranklist_page(index).await?;
+            to_json(&page)
+        }
+        FrontMessage::GetRanklistPageCount => {
+            let count = get_ranklist_page_count().await;
+            to_json(count)
+        }
+        FrontMessage::ChangeNickname(nickname) => {
+            let uid = logined_user.map(|x| x.uid).fuck()?;
+            change_nickname(uid, nickname).await?;
+            to_json(())
+        }
+        FrontMessage::ChangeEmail(email) => {
+            let uid = logined_user.map(|x| x.uid).fuck()?;
+            change_email(uid, email).await?;
+            to_json(())
+        }
+        FrontMessage::ChangePassword(change) => {
+            let uid = logined_user.map(|x| x.uid).fuck()?;
+            change_password(uid, change).await?;
+            to_json(())
+        }
+        FrontMessage::GrantRole(uid, role) => {
+            require(Permission::ManageUsers)?;
+            if role == Role::Banned && logined_user.as_ref().is_some_and(|user| user.uid == uid) {
+                return Err(ServerError::Fuck);
+            }
+            grant_role(uid, role).await?;
+            to_json(())
+        }
+        FrontMessage::RevokeRole(uid, role) => {
+            require(Permission::ManageUsers)?;
+            // keeps admins from locking themselves out
+            if role == Role::Admin && logined_user.as_ref().is_some_and(|user| user.uid == uid) {
+                return Err(ServerError::Fuck);
+            }
+            revoke_role(uid, role).await?;
+            to_json(())
+        }
+        FrontMessage::ListUsers(query) => {
+            require(Permission::ManageUsers)?;
+            let list = list_users(query).await?;
+            to_json(&list)
+        }
+        FrontMessage::ForceLogout(uid) => {
+            require(Permission::ManageUsers)?;
+            force_logout(uid).await?;
+            to_json(())
+        }
+        FrontMessage::ResetPassword(uid, password) => {
+            require(Permission::ManageUsers)?;
+            reset_password(uid, password).await?;
+            to_json(())
+        }
+        FrontMessage::GetRecords(query) => {
+            let records = search_records(&query, RECORDS_PAGE_SIZE).await?;
+            to_json(&records)
+        }
+        FrontMessage::CreateBackup => {
+            require(Permission::Backup)?;
+            let name = start_backup().await?;
+            to_json(name)
+        }
         FrontMessage::RegisterUser(registration) => {
             let uid = tokio::spawn(user_register(registration)).await.unwrap()?;
             to_json(uid)
@@ -196,8 +334,7 @@ pub async fn receive_front_message(
         }
         FrontMessage::GetProblemsPage(index) => {
             let page = get_problems_page(index).await?;
-            let page: Vec<_> = page.iter().map(|d| d.profile()).collect();
-            to_json(&page)
+            to_json(&*page)
         }
         FrontMessage::GetProblemsPageCount => {
             let count = get_page_count().await?;
@@ -4,7 +4,7 @@ use super::*;
 use crate::problem::Problem;
 use rust_embed::RustEmbed;
 use shared::problem::{Pid, ProblemFile, Testcase};
-use shared::user::UserRegistration;
+use shared::user::{Role, UserRegistration};
 use uuid::Uuid;
 
 pub async fn users() -> eyre::Result<()> {
@@ -16,7 +16,7 @@ pub async fn users() -> eyre::Result<()> {
     };
 
     let uid = crate::user::user_register(author1).await?;
-    crate::user::set_su(uid).await?;
+    crate::user::grant_role(uid, Role::Admin).await?;
 
     let author2 = UserRegistration {
         email: "2816055869".into(),
@@ -26,7 +26,7 @@ pub async fn users() -> eyre::Result<()> {
     };
 
     let uid = crate::user::user_register(author2).await?;
-    crate::user::set_su(uid).await?;
+    crate::user::grant_role(uid, Role::Admin).await?;
 
     let visitor = UserRegistration {
         email: "xxx@mygoj.ac".into(),
@@ -17,6 +17,8 @@ impl EmbedProblem for SimpleFs {
             time_limit: 0,
             testcases: vec![].into(),
             files: vec![].into(),
+            run_limits: Default::default(),
+            file_io: None,
         }
     }
 }
@@ -37,6 +39,8 @@ impl EmbedProblem for ComplexFs {
             time_limit: 100,
             testcases: vec![].into(),
             files: vec![].into(),
+            run_limits: Default::default(),
+            file_io: None,
         }
     }
 }
@@ -59,14 +63,18 @@ impl EmbedProblem for ApB {
                 Testcase {
                     input_file: "1.in".into(),
                     output_file: "1.out".into(),
+                    is_sample: true,
                 },
                 Testcase {
                     input_file: "2.in".into(),
                     output_file: "2.out".into(),
+                    is_sample: false,
                 },
             ]
             .into(),
             files: vec![].into(),
+            run_limits: Default::default(),
+            file_io: None,
         }
     }
 }
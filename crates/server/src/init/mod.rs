@@ -1,6 +1,4 @@
 use crate::db::*;
-use futures_util::StreamExt;
-use sqlx::Executor;
 use std::io::{Error as IoError, ErrorKind};
 use std::path::Path;
 use tokio::fs;
@@ -42,11 +40,7 @@ pub async fn init_db(path: impl AsRef<Path>) -> eyre::Result<()> {
     fs::write(path, "").await?;
 
     database_connect(path).await?;
-    let db = DB.get().unwrap();
-    let mut stream = db.execute_many(include_str!("../sql/create.sql"));
-    while let Some(ret) = stream.next().await {
-        let _ = ret?;
-    }
+    crate::migrate::migrate().await?;
 
     #[cfg(debug_assertions)]
     {
@@ -0,0 +1,448 @@
+mod queue;
+
+use super::{Fuck, ServerError};
+use queue::JudgeQueue;
+use shared::judge::*;
+use shared::record::*;
+use static_init::dynamic;
+use std::collections::{HashMap, VecDeque, hash_map};
+use std::time::Duration;
+use tokio::sync::{Mutex, watch};
+use uuid::Uuid;
+
+#[dynamic]
+static JUDGE_QUEUE: Mutex<JudgeQueue> = Mutex::new(JudgeQueue::default());
+
+#[dynamic]
+static QUEUE_CHANGED: watch::Sender<()> = watch::Sender::new(());
+
+#[dynamic]
+static SIGNALS: Mutex<HashMap<Uuid, JudgeMachineSignal>> = Mutex::new(HashMap::new());
+
+/// A task handed out to a judge machine, kept alive by its signals and results.
+#[derive(Debug, Clone, Copy)]
+struct Lease {
+    machine: Uuid,
+    renewed: u64,
+}
+
+#[dynamic]
+static LEASES: Mutex<HashMap<JudgeTask, Lease>> = Mutex::new(HashMap::new());
+
+/// The machine each record was last handed out to and when, that machine keeps
+/// the outputs of its failed cases for a while.
+#[dynamic]
+static JUDGED_BY: Mutex<HashMap<Rid, (Uuid, u64)>> = Mutex::new(HashMap::new());
+
+/// Outputs asked for but not yet sent to each machine.
+#[dynamic]
+static UPLOAD_REQUESTS: Mutex<HashMap<Uuid, Vec<(Rid, usize)>>> = Mutex::new(HashMap::new());
+
+const MACHINE_TIMEOUT: u64 = 4000;
+const LEASE_TIMEOUT: u64 = 30_000;
+/// How long to remember who judged a record, judges drop old outputs long before.
+const JUDGED_BY_RETENTION: u64 = 24 * 60 * 60 * 1000;
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+pub async fn init_queue() -> eyre::Result<()> {
+    let db = crate::db::DB.get().unwrap();
+    let waiting_flag = RecordFlag::Waiting.as_str();
+    let ret = sqlx::query!(
+        "SELECT rid,priority FROM records WHERE flag=$1",
+        waiting_flag
+    )
+    .fetch_all(db)
+    .await?;
+    for rec in ret {
+        let priority = rec
+            .priority
+            .as_deref()
+            .and_then(JudgePriority::parse)
+            .unwrap_or(JudgePriority::Submission);
+        let rid = Rid(rec.rid.unwrap() as u64);
+        new_record(rid, get_record(rid).await?, priority).await?;
+    }
+    Ok(())
+}
+
+pub async fn enqueue(queued: QueuedTask) {
+    JUDGE_QUEUE.lock().await.push_back(queued);
+    QUEUE_CHANGED.send_replace(());
+}
+
+pub async fn enqueue_front(queued: QueuedTask) {
+    JUDGE_QUEUE.lock().await.push_front(queued);
+    QUEUE_CHANGED.send_replace(());
+}
+
+pub async fn queued_tasks() -> Vec<QueuedTask> {
+    JUDGE_QUEUE.lock().await.list()
+}
+
+pub async fn track_judge_machines() {
+    let mut offline = Vec::new();
+    let mut expired = Vec::new();
+    loop {
+        let now = now();
+        let mut signals = SIGNALS.lock().await;
+        offline.clear();
+        for sig in signals.values() {
+            if now.saturating_sub(sig.timestamp) > MACHINE_TIMEOUT {
+                offline.push(sig.uuid);
+            }
+        }
+        for uuid in &offline {
+            signals.remove(uuid);
+            tracing::info!("judge machine offline {}", uuid);
+        }
+        drop(signals);
+
+        let mut requests = UPLOAD_REQUESTS.lock().await;
+        for uuid in &offline {
+            requests.remove(uuid);
+        }
+        drop(requests);
+
+        JUDGED_BY.lock().await.retain(|_, (machine, time)| {
+            !offline.contains(machine) && now.saturating_sub(*time) < JUDGED_BY_RETENTION
+        });
+
+        let mut leases = LEASES.lock().await;
+        expired.clear();
+        leases.retain(|task, lease| {
+            let lost = offline.contains(&lease.machine)
+                || now.saturating_sub(lease.renewed) > LEASE_TIMEOUT;
+            if lost {
+                tracing::info!("lease of {:?} on {} lost", task, lease.machine);
+                expired.push(*task);
+            }
+            !lost
+        });
+        drop(leases);
+
+        for task in &expired {
+            let ret = match *task {
+                JudgeTask::Record(rid) => requeue_record(rid, true).await,
+                JudgeTask::Run(id) => requeue_run(id, true).await,
+            };
+            if let Err(err) = ret {
+                tracing::error!("failed to requeue {task:?} {err:?}");
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Asks the machine that judged the record for the output of a testcase,
+/// failing if that machine is not online.
+pub async fn request_case_output(rid: Rid, idx: usize) -> Result<(), ServerError> {
+    let (machine, _) = *JUDGED_BY
+        .lock()
+        .await
+        .get(&rid)
+        .ok_or(ServerError::NotFound)?;
+    if !SIGNALS.lock().await.contains_key(&machine) {
+        return Err(ServerError::NotFound);
+    }
+    UPLOAD_REQUESTS
+        .lock()
+        .await
+        .entry(machine)
+        .or_default()
+        .push((rid, idx));
+    QUEUE_CHANGED.send_replace(());
+    Ok(())
+}
+
+async fn generate_command(machine: Uuid) -> Result<JudgeCommand, ServerError> {
+    if let Some(requests) = UPLOAD_REQUESTS.lock().await.get_mut(&machine)
+        && let Some((rid, idx)) = requests.pop()
+    {
+        return Ok(JudgeCommand::UploadCaseOutput(rid, idx));
+    }
+    let capacity = SIGNALS
+        .lock()
+        .await
+        .get(&machine)
+        .map_or(1, |sig| sig.capacity.max(1));
+    let running = LEASES
+        .lock()
+        .await
+        .values()
+        .filter(|lease| lease.machine == machine)
+        .count();
+    if running >= capacity as usize {
+        return Ok(JudgeCommand::Null);
+    }
+    let queued = JUDGE_QUEUE.lock().await.pop();
+    let Some(QueuedTask { task, .. }) = queued else {
+        return Ok(JudgeCommand::Null);
+    };
+    let lease = Lease {
+        machine,
+        renewed: now(),
+    };
+    LEASES.lock().await.insert(task, lease);
+    match task {
+        JudgeTask::Record(rid) => {
+            JUDGED_BY.lock().await.insert(rid, (machine, now()));
+            forget_case_outputs(rid).await;
+            update_record(rid, RecordStatus::Compiling).await?;
+            Ok(JudgeCommand::Judge(rid))
+        }
+        JudgeTask::Run(id) => {
+            update_run(id, CustomRunStatus::Running).await?;
+            Ok(JudgeCommand::Run(id))
+        }
+    }
+}
+
+/// Gives back what a command handed out when it never reached the machine,
+/// so its task is queued again right away without counting an attempt.
+async fn take_back_command(machine: Uuid, command: JudgeCommand) {
+    let task = match command {
+        JudgeCommand::Judge(rid) => JudgeTask::Record(rid),
+        JudgeCommand::Run(id) => JudgeTask::Run(id),
+        JudgeCommand::UploadCaseOutput(rid, idx) => {
+            if let Some(requests) = UPLOAD_REQUESTS.lock().await.get_mut(&machine) {
+                requests.push((rid, idx));
+            }
+            return;
+        }
+        JudgeCommand::Null => return,
+    };
+    if release_lease(task, machine).await.is_err() {
+        return;
+    }
+    let ret = match task {
+        JudgeTask::Record(rid) => requeue_record(rid, false).await,
+        JudgeTask::Run(id) => requeue_run(id, false).await,
+    };
+    if let Err(err) = ret {
+        tracing::error!("failed to requeue {task:?} {err:?}");
+    }
+}
+
+/// Refreshes the lease of a task, failing if it is not handed out to the machine anymore.
+async fn renew_lease(task: JudgeTask, machine: Uuid) -> Result<(), ServerError> {
+    let mut leases = LEASES.lock().await;
+    let lease = leases.get_mut(&task).fuck()?;
+    if lease.machine != machine {
+        return Err(ServerError::Fuck);
+    }
+    lease.renewed = now();
+    Ok(())
+}
+
+async fn release_lease(task: JudgeTask, machine: Uuid) -> Result<(), ServerError> {
+    let mut leases = LEASES.lock().await;
+    if leases.get(&task).fuck()?.machine != machine {
+        return Err(ServerError::Fuck);
+    }
+    leases.remove(&task);
+    Ok(())
+}
+
+use super::problem::{problem_data, send_problem_file};
+use super::record::{
+    forget_case_outputs, get_record, new_record, receive_case_output, requeue_record,
+    update_record, update_record_single,
+};
+use super::run::{get_run, requeue_run, update_run};
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{
+    WebSocketUpgrade,
+    ws::{Message as WsMessage, WebSocket},
+};
+use axum::response::Response;
+
+pub async fn receive_message(Json(msg): Json<JudgeMessage>) -> Result<Response, ServerError> {
+    fn to_json<T: serde::Serialize>(val: T) -> Result<Response, ServerError> {
+        Ok(Response::new(Body::new(
+            serde_json::to_string_pretty(&val).map_err(ServerError::into_internal)?,
+        )))
+    }
+    match msg {
+        JudgeMessage::Signal(sig) => {
+            let command = receive_signal(sig).await?;
+            to_json(command)
+        }
+        JudgeMessage::GetProblemData(pid) => {
+            let problem_data = problem_data(pid).await?;
+            to_json(problem_data)
+        }
+        JudgeMessage::GetProblemFile(pid, filename) => send_problem_file(pid, &filename).await,
+        JudgeMessage::GetRecord(rid) => {
+            let record = get_record(rid).await?;
+            to_json(record)
+        }
+        JudgeMessage::GetCustomRun(id) => {
+            let run = get_run(id).await?;
+            to_json(run)
+        }
+        msg => {
+            receive_result(msg).await?;
+            to_json(())
+        }
+    }
+}
+
+async fn receive_result(msg: JudgeMessage) -> Result<(), ServerError> {
+    match msg {
+        JudgeMessage::SendSingleJudgeResult(machine, rid, idx, res, output) => {
+            renew_lease(JudgeTask::Record(rid), machine).await?;
+            update_record_single(rid, idx, res, output).await?;
+        }
+        JudgeMessage::SendCompileResult(machine, rid, res) => {
+            renew_lease(JudgeTask::Record(rid), machine).await?;
+            let status = match res {
+                CompileResult::Compiled => {
+                    RecordStatus::Running(vec![
+                        const { None };
+                        problem_data(get_record(rid).await?.pid)
+                            .await?
+                            .testcases
+                            .len()
+                    ])
+                }
+                CompileResult::Error(ce) => {
+                    release_lease(JudgeTask::Record(rid), machine).await?;
+                    RecordStatus::CompileError(ce)
+                }
+            };
+            tokio::spawn(update_record(rid, status)).await.unwrap()?;
+        }
+        JudgeMessage::SendAllJudgeResults(machine, rid, res) => {
+            release_lease(JudgeTask::Record(rid), machine).await?;
+            tokio::spawn(update_record(rid, RecordStatus::Completed(res)))
+                .await
+                .unwrap()?;
+        }
+        JudgeMessage::SendCustomRunResult(machine, id, status) => {
+            release_lease(JudgeTask::Run(id), machine).await?;
+            update_run(id, status).await?;
+        }
+        JudgeMessage::SendCaseOutput(machine, rid, idx, output) => {
+            if JUDGED_BY.lock().await.get(&rid).map(|x| x.0) != Some(machine) {
+                return Err(ServerError::Fuck);
+            }
+            receive_case_output(rid, idx, output).await?;
+        }
+        _ => return Err(ServerError::Fuck),
+    }
+    Ok(())
+}
+
+async fn update_signal(signal: JudgeMachineSignal) {
+    let uuid = signal.uuid;
+    {
+        let mut leases = LEASES.lock().await;
+        let now = now();
+        let tasks = signal.tasks.iter().map(|rid| JudgeTask::Record(*rid));
+        let runs = signal.runs.iter().map(|id| JudgeTask::Run(*id));
+        for task in tasks.chain(runs) {
+            if let Some(lease) = leases.get_mut(&task)
+                && lease.machine == uuid
+            {
+                lease.renewed = now;
+            }
+        }
+    }
+
+    let mut signals = SIGNALS.lock().await;
+    // tracing::info!("received signal {:?}", &signal);
+    if let hash_map::Entry::Vacant(e) = signals.entry(uuid) {
+        tracing::info!("new judge machine online {}", uuid);
+        e.insert(signal);
+    } else {
+        *signals.get_mut(&uuid).unwrap() = signal;
+    }
+}
+
+pub async fn receive_signal(signal: JudgeMachineSignal) -> Result<JudgeCommand, ServerError> {
+    let uuid = signal.uuid;
+    update_signal(signal).await;
+    let command = generate_command(uuid).await?;
+    Ok(command)
+}
+
+pub async fn ws(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(handle_socket)
+}
+
+async fn send_command(socket: &mut WebSocket, command: &JudgeCommand) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(command).unwrap();
+    socket.send(WsMessage::Text(text.into())).await
+}
+
+/// Serves a judge machine over a websocket, pushing records to it as soon
+/// as they enter the queue instead of waiting for its next signal.
+async fn handle_socket(mut socket: WebSocket) {
+    let mut queue_changed = QUEUE_CHANGED.subscribe();
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    let mut machine = None;
+    loop {
+        tokio::select! {
+            msg = socket.recv() => {
+                let Some(Ok(msg)) = msg else {
+                    break;
+                };
+                let WsMessage::Text(text) = msg else {
+                    continue;
+                };
+                let msg = match serde_json::from_str::<JudgeMessage>(&text) {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        tracing::warn!("bad judge message {err:?}");
+                        break;
+                    }
+                };
+                if let JudgeMessage::Signal(sig) = msg {
+                    machine = Some(sig.uuid);
+                    update_signal(sig).await;
+                } else if let Err(err) = receive_result(msg).await {
+                    tracing::warn!("fail to receive judge result {err:?}");
+                }
+            }
+            _ = queue_changed.changed() => {}
+            _ = tick.tick() => {}
+        }
+        let Some(machine) = machine else {
+            continue;
+        };
+        loop {
+            let command = match generate_command(machine).await {
+                Ok(command) => command,
+                Err(err) => {
+                    tracing::error!("fail to generate command {err:?}");
+                    break;
+                }
+            };
+            if command == JudgeCommand::Null {
+                break;
+            }
+            if send_command(&mut socket, &command).await.is_err() {
+                tracing::info!("judge socket of {machine} closed");
+                take_back_command(machine, command).await;
+                return;
+            }
+        }
+    }
+    if let Some(machine) = machine {
+        tracing::info!("judge socket of {machine} closed");
+    }
+}
+
+pub async fn judge_machines() -> Result<Vec<JudgeMachineSignal>, ServerError> {
+    let res = SIGNALS
+        .lock()
+        .await
+        .iter()
+        .map(|x| x.1.clone())
+        .collect::<Vec<_>>();
+    Ok(res)
+}
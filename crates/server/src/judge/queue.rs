@@ -0,0 +1,152 @@
+use super::*;
+use shared::user::Uid;
+use std::collections::BTreeMap;
+
+/// Tasks waiting for a judge machine. Lower priorities are served first,
+/// and users take turns within the same priority so nobody can starve others.
+#[derive(Debug, Default, Clone)]
+pub struct JudgeQueue {
+    levels: BTreeMap<JudgePriority, FairQueue>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct FairQueue {
+    users: VecDeque<Uid>,
+    tasks: HashMap<Uid, VecDeque<JudgeTask>>,
+}
+
+impl FairQueue {
+    fn push_back(&mut self, uid: Uid, task: JudgeTask) {
+        let tasks = self.tasks.entry(uid).or_default();
+        if tasks.is_empty() {
+            self.users.push_back(uid);
+        }
+        tasks.push_back(task);
+    }
+
+    fn push_front(&mut self, uid: Uid, task: JudgeTask) {
+        let tasks = self.tasks.entry(uid).or_default();
+        if tasks.is_empty() {
+            self.users.push_front(uid);
+        } else {
+            self.users.retain(|x| *x != uid);
+            self.users.push_front(uid);
+        }
+        tasks.push_front(task);
+    }
+
+    fn pop(&mut self) -> Option<(Uid, JudgeTask)> {
+        let uid = self.users.pop_front()?;
+        let tasks = self.tasks.get_mut(&uid).unwrap();
+        let task = tasks.pop_front().unwrap();
+        if tasks.is_empty() {
+            self.tasks.remove(&uid);
+        } else {
+            self.users.push_back(uid);
+        }
+        Some((uid, task))
+    }
+}
+
+impl JudgeQueue {
+    pub fn push_back(&mut self, queued: QueuedTask) {
+        let level = self.levels.entry(queued.priority).or_default();
+        level.push_back(queued.uid, queued.task);
+    }
+
+    pub fn push_front(&mut self, queued: QueuedTask) {
+        let level = self.levels.entry(queued.priority).or_default();
+        level.push_front(queued.uid, queued.task);
+    }
+
+    pub fn pop(&mut self) -> Option<QueuedTask> {
+        let mut entry = self.levels.first_entry()?;
+        let priority = *entry.key();
+        let (uid, task) = entry.get_mut().pop().unwrap();
+        if entry.get().users.is_empty() {
+            entry.remove();
+        }
+        Some(QueuedTask {
+            task,
+            uid,
+            priority,
+        })
+    }
+
+    /// Lists the queue in the order it is going to be served.
+    pub fn list(&self) -> Vec<QueuedTask> {
+        let mut queue = self.clone();
+        let mut ret = Vec::new();
+        while let Some(rec) = queue.pop() {
+            ret.push(rec);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(uid: u64, id: u64, priority: JudgePriority) -> QueuedTask {
+        QueuedTask {
+            task: JudgeTask::Run(RunId(id)),
+            uid: Uid(uid),
+            priority,
+        }
+    }
+
+    fn order(queue: &JudgeQueue) -> Vec<u64> {
+        queue
+            .list()
+            .into_iter()
+            .map(|queued| match queued.task {
+                JudgeTask::Run(id) => id.0,
+                JudgeTask::Record(rid) => rid.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn users_take_turns() {
+        let mut queue = JudgeQueue::default();
+        for id in 0..3 {
+            queue.push_back(queued(1, id, JudgePriority::Submission));
+        }
+        queue.push_back(queued(2, 10, JudgePriority::Submission));
+        queue.push_back(queued(3, 20, JudgePriority::Submission));
+        queue.push_back(queued(2, 11, JudgePriority::Submission));
+        assert_eq!(order(&queue), [0, 10, 20, 1, 11, 2]);
+    }
+
+    #[test]
+    fn priorities_served_in_order() {
+        let mut queue = JudgeQueue::default();
+        queue.push_back(queued(1, 0, JudgePriority::Run));
+        queue.push_back(queued(1, 1, JudgePriority::Rejudge));
+        queue.push_back(queued(2, 2, JudgePriority::Submission));
+        queue.push_back(queued(1, 3, JudgePriority::Submission));
+        assert_eq!(order(&queue), [2, 3, 1, 0]);
+        assert_eq!(queue.pop().unwrap().priority, JudgePriority::Submission);
+    }
+
+    #[test]
+    fn contests_go_before_submissions() {
+        let mut queue = JudgeQueue::default();
+        queue.push_back(queued(1, 0, JudgePriority::Submission));
+        queue.push_back(queued(2, 1, JudgePriority::Rejudge));
+        queue.push_back(queued(1, 2, JudgePriority::Contest));
+        assert_eq!(order(&queue), [2, 0, 1]);
+        assert_eq!(queue.pop().unwrap().priority, JudgePriority::Contest);
+    }
+
+    #[test]
+    fn push_front_goes_first() {
+        let mut queue = JudgeQueue::default();
+        queue.push_back(queued(1, 0, JudgePriority::Submission));
+        queue.push_back(queued(2, 1, JudgePriority::Submission));
+        queue.push_back(queued(2, 2, JudgePriority::Submission));
+        queue.push_front(queued(2, 3, JudgePriority::Submission));
+        assert_eq!(order(&queue), [3, 0, 1, 2]);
+    }
+}
@@ -4,9 +4,13 @@ mod front;
 mod judge;
 mod problem;
 mod record;
+mod run;
 mod user;
 
+pub mod backup;
 pub mod init;
+pub mod maintenance;
+pub mod migrate;
 pub mod serve;
 
 use error::Fuck;
@@ -17,3 +21,17 @@ use std::path::PathBuf;
 pub fn storage_dir() -> PathBuf {
     dirs::home_dir().unwrap().join("mygoj")
 }
+
+/// Locks the storage directory, so only one of the server and the maintenance
+/// commands uses it at a time. The lock lasts as long as the returned file.
+pub fn lock_storage() -> eyre::Result<std::fs::File> {
+    let path = storage_dir().join("lock");
+    let file = std::fs::File::create(&path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(std::fs::TryLockError::WouldBlock) => {
+            eyre::bail!("another mygoj process is using {}", storage_dir().display())
+        }
+        Err(std::fs::TryLockError::Error(err)) => Err(err.into()),
+    }
+}
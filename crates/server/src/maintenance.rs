@@ -0,0 +1,147 @@
+//! Administrative tasks run from the command line. They work on the database
+//! directly, so the server has to be stopped, otherwise its caches go stale.
+//! `lock_storage` keeps them from running beside it.
+
+use super::*;
+use crate::problem::{Problem, ProblemProfile};
+use shared::user::{Role, Uid, UserRegistration};
+use std::collections::HashSet;
+use tokio::fs;
+use uuid::Uuid;
+
+pub async fn connect() -> eyre::Result<()> {
+    let path = storage_dir().join("data.db");
+    if !fs::try_exists(&path).await? {
+        eyre::bail!("no database at {}, run init first", path.display());
+    }
+    db::database_connect(path.to_str().unwrap()).await
+}
+
+async fn find_user(ident: &str) -> eyre::Result<Uid> {
+    user::find_user(ident)
+        .await?
+        .ok_or_else(|| eyre::eyre!("no user {ident}"))
+}
+
+pub async fn add_user(reg: UserRegistration, admin: bool) -> eyre::Result<Uid> {
+    let uid = user::user_register(reg).await?;
+    if admin {
+        user::grant_role(uid, Role::Admin).await?;
+    }
+    Ok(uid)
+}
+
+pub async fn set_su(ident: &str) -> eyre::Result<Uid> {
+    let uid = find_user(ident).await?;
+    user::grant_role(uid, Role::Admin).await?;
+    Ok(uid)
+}
+
+pub async fn reset_password(ident: &str, password: &str) -> eyre::Result<Uid> {
+    let uid = find_user(ident).await?;
+    user::reset_password(uid, password.into()).await?;
+    Ok(uid)
+}
+
+pub async fn list_problems() -> eyre::Result<Vec<ProblemProfile>> {
+    let problems = problem::all_problems().await?;
+    Ok(problems.iter().map(Problem::profile).collect())
+}
+
+/// Rebuilds the columns kept beside the json of each user, which the queries
+/// search by. The caches in memory are built again when the server starts.
+pub async fn rebuild_caches() -> eyre::Result<u64> {
+    Ok(user::rebuild_user_columns().await?)
+}
+
+/// Looks for missing or mismatched files and dangling rows, returns what is wrong.
+pub async fn check_storage() -> eyre::Result<Vec<String>> {
+    let mut issues = Vec::new();
+    let problems = problem::all_problems().await?;
+    let root = problem::problems_storage_path();
+
+    for problem in &problems {
+        let pid = &problem.pid;
+        let dir = root.join(&pid.0);
+        if !fs::try_exists(&dir).await? {
+            issues.push(format!("problem {pid} has no storage directory"));
+            continue;
+        }
+        for file in problem.files.iter() {
+            let path = dir.join(file.uuid.to_string());
+            match fs::metadata(&path).await {
+                Ok(meta) if meta.len() != file.size => issues.push(format!(
+                    "file {} of {pid} has {} bytes instead of {}",
+                    file.path,
+                    meta.len(),
+                    file.size
+                )),
+                Ok(_) => {}
+                Err(_) => issues.push(format!("file {} of {pid} is missing", file.path)),
+            }
+        }
+        for (idx, testcase) in problem.testcases.iter().enumerate() {
+            for name in [&testcase.input_file, &testcase.output_file] {
+                if !problem.files.iter().any(|file| file.path == *name) {
+                    issues.push(format!("testcase {idx} of {pid} uses unknown file {name}"));
+                }
+            }
+        }
+        let known: HashSet<Uuid> = problem.files.iter().map(|file| file.uuid).collect();
+        let mut entries = fs::read_dir(&dir).await?;
+        let mut unused = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let used = name
+                .to_str()
+                .and_then(|name| name.parse::<Uuid>().ok())
+                .is_some_and(|uuid| known.contains(&uuid));
+            if !used {
+                unused += 1;
+            }
+        }
+        if unused > 0 {
+            issues.push(format!("{unused} unused files in storage of {pid}"));
+        }
+    }
+
+    if fs::try_exists(&root).await? {
+        let pids: HashSet<&str> = problems.iter().map(|p| p.pid.0.as_str()).collect();
+        let mut entries = fs::read_dir(&root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !pids.contains(&*name) {
+                issues.push(format!("storage directory {name} belongs to no problem"));
+            }
+        }
+    }
+
+    let db = db::DB.get().unwrap();
+    let orphans = sqlx::query!(
+        "SELECT rid FROM records WHERE pid NOT IN (SELECT pid FROM problems) OR uid NOT IN (SELECT uid FROM users)"
+    )
+    .fetch_all(db)
+    .await?;
+    for row in orphans {
+        issues.push(format!(
+            "record {} belongs to a missing problem or user",
+            row.rid
+        ));
+    }
+
+    Ok(issues)
+}
+
+/// Removes files no problem refers to anymore, returns how many.
+pub async fn clean_problem_files() -> eyre::Result<u64> {
+    let mut count = 0;
+    for problem in problem::all_problems().await? {
+        let dir = problem::problems_storage_path().join(&problem.pid.0);
+        if !fs::try_exists(&dir).await? {
+            continue;
+        }
+        count += problem::files::clean_unused_problem_files(&problem.pid).await?;
+    }
+    Ok(count)
+}
@@ -0,0 +1,126 @@
+//! Versioned changes of the database schema. A released migration is never
+//! edited, changes go into a new one appended to `MIGRATIONS`.
+
+use super::db::DB;
+use sqlx::{Row, SqliteConnection};
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create",
+        sql: include_str!("sql/migrations/0001_create.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "priority",
+        sql: include_str!("sql/migrations/0002_priority.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "normalize",
+        sql: include_str!("sql/migrations/0003_normalize.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "split_json",
+        sql: include_str!("sql/migrations/0004_split_json.sql"),
+    },
+];
+
+const CREATE_VERSION_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_version(
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_time INT NOT NULL
+)";
+
+async fn has_table(con: &mut SqliteConnection, name: &str) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=$1")
+        .bind(name)
+        .fetch_one(con)
+        .await?;
+    Ok(row.get::<i64, _>(0) > 0)
+}
+
+/// Databases made before migrations existed have the tables of the first one
+/// but no version table.
+async fn schema_version(con: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    if has_table(&mut *con, "schema_version").await? {
+        let row = sqlx::query("SELECT COALESCE(MAX(version),0) FROM schema_version")
+            .fetch_one(con)
+            .await?;
+        Ok(row.get(0))
+    } else if has_table(&mut *con, "users").await? {
+        Ok(1)
+    } else {
+        Ok(0)
+    }
+}
+
+fn after(version: i64) -> eyre::Result<Vec<&'static Migration>> {
+    let latest = MIGRATIONS.last().map_or(0, |m| m.version);
+    if version > latest {
+        eyre::bail!("database is at schema version {version}, newer than {latest} of this build");
+    }
+    Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+}
+
+/// Fails if the database is newer than this build knows.
+pub async fn check_version(con: &mut SqliteConnection) -> eyre::Result<i64> {
+    let version = schema_version(con).await?;
+    after(version)?;
+    Ok(version)
+}
+
+/// Migrations not applied to the database yet, in order.
+pub async fn pending() -> eyre::Result<Vec<&'static Migration>> {
+    let mut con = DB.get().unwrap().acquire().await?;
+    after(schema_version(&mut con).await?)
+}
+
+/// Applies the pending migrations, each in its own transaction, returns them.
+pub async fn migrate() -> eyre::Result<Vec<&'static Migration>> {
+    let db = DB.get().unwrap();
+    {
+        let mut txn = db.begin_with("BEGIN IMMEDIATE").await?;
+        if !has_table(&mut txn, "schema_version").await? {
+            let version = schema_version(&mut txn).await?;
+            sqlx::raw_sql(CREATE_VERSION_TABLE)
+                .execute(&mut *txn)
+                .await?;
+            for migration in MIGRATIONS.iter().take_while(|m| m.version <= version) {
+                record(&mut txn, migration).await?;
+            }
+        }
+        txn.commit().await?;
+    }
+    let mut applied = Vec::new();
+    loop {
+        let mut txn = db.begin_with("BEGIN IMMEDIATE").await?;
+        let version = schema_version(&mut txn).await?;
+        let Some(migration) = after(version)?.first().copied() else {
+            break;
+        };
+        tracing::info!("apply migration {} {}", migration.version, migration.name);
+        sqlx::raw_sql(migration.sql).execute(&mut *txn).await?;
+        record(&mut txn, migration).await?;
+        txn.commit().await?;
+        applied.push(migration);
+    }
+    Ok(applied)
+}
+
+async fn record(con: &mut SqliteConnection, migration: &Migration) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO schema_version (version,name,applied_time) VALUES ($1,$2,$3)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(con)
+        .await?;
+    Ok(())
+}
@@ -1,10 +1,50 @@
 use super::*;
 use crate::db::DB;
-use sqlx::{FromRow, SqlitePool, sqlite::SqliteRow};
+use sqlx::{FromRow, Row, SqlitePool, sqlite::SqliteRow};
 
+/// What of a problem is kept in its json, everything else has a column.
+#[derive(Serialize, Deserialize)]
+struct ProblemDetail {
+    statement: Arc<String>,
+    testcases: Arc<Vec<Testcase>>,
+    files: Arc<Vec<ProblemFile>>,
+    #[serde(default)]
+    run_limits: RunLimits,
+    #[serde(default)]
+    file_io: Option<FileIo>,
+}
+
+impl ProblemDetail {
+    fn new(problem: &Problem) -> Self {
+        ProblemDetail {
+            statement: problem.statement.clone(),
+            testcases: problem.testcases.clone(),
+            files: problem.files.clone(),
+            run_limits: problem.run_limits,
+            file_io: problem.file_io.clone(),
+        }
+    }
+}
+
+/// Reads the columns of `SELECT *` from problems.
 impl FromRow<'_, SqliteRow> for Problem {
     fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
-        shared::from_json_in_row(row)
+        let detail: ProblemDetail = shared::from_json_in_row(row)?;
+        Ok(Problem {
+            pid: Pid::new(row.try_get::<&str, _>("pid")?),
+            created_time: row.try_get("created_time")?,
+            owner: row
+                .try_get::<Option<i64>, _>("owner")?
+                .map(|owner| Uid(owner as u64)),
+            title: row.try_get::<&str, _>("title")?.into(),
+            statement: detail.statement,
+            memory_limit: row.try_get("memory_limit")?,
+            time_limit: row.try_get("time_limit")?,
+            testcases: detail.testcases,
+            files: detail.files,
+            run_limits: detail.run_limits,
+            file_io: detail.file_io,
+        })
     }
 }
 
@@ -12,13 +52,17 @@ impl Problem {
     pub async fn insert_db(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
         let pid = self.pid.0.as_str();
         let owner = self.owner.map(|x| x.0 as i64);
-        let json = serde_json::to_string(self).unwrap();
+        let json = serde_json::to_string(&ProblemDetail::new(self)).unwrap();
         let time = self.created_time;
+        let title = self.title.as_str();
         sqlx::query!(
-            "INSERT INTO problems (pid,owner,created_time,json) VALUES ($1,$2,$3,$4)",
+            "INSERT INTO problems (pid,owner,created_time,title,time_limit,memory_limit,json) VALUES ($1,$2,$3,$4,$5,$6,$7)",
             pid,
             owner,
             time,
+            title,
+            self.time_limit,
+            self.memory_limit,
             json
         )
         .execute(pool)
@@ -29,26 +73,66 @@ impl Problem {
 
 pub async fn get_problem(pid: &Pid) -> Result<Arc<Problem>, sqlx::Error> {
     tracing::trace!("DB fetch problem {pid}");
-    let pid = pid.0.as_str();
-    let json = sqlx::query!("SELECT (json) FROM problems WHERE pid=$1", pid)
+    let p = sqlx::query_as("SELECT * FROM problems WHERE pid=$1")
+        .bind(pid.0.as_str())
         .fetch_one(DB.get().unwrap())
-        .await?
-        .json
-        .unwrap();
-
-    let p = serde_json::from_str(&json).unwrap();
+        .await?;
     Ok(Arc::new(p))
 }
 
+pub async fn problems_page(offset: i64, limit: i64) -> Result<Vec<ProblemProfile>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT pid,title FROM problems LIMIT $1 OFFSET $2",
+        limit,
+        offset
+    )
+    .fetch_all(DB.get().unwrap())
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| ProblemProfile {
+            pid: Pid::new(&row.pid.unwrap()),
+            title: row.title.into(),
+        })
+        .collect())
+}
+
+pub async fn owned_problems(owner: Uid) -> Result<Vec<ProblemProfile>, sqlx::Error> {
+    let owner = owner.0 as i64;
+    let rows = sqlx::query!(
+        "SELECT pid,title FROM problems WHERE owner=$1 ORDER BY created_time",
+        owner
+    )
+    .fetch_all(DB.get().unwrap())
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| ProblemProfile {
+            pid: Pid::new(&row.pid.unwrap()),
+            title: row.title.into(),
+        })
+        .collect())
+}
+
+pub async fn all_problems() -> Result<Vec<Problem>, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM problems ORDER BY pid")
+        .fetch_all(DB.get().unwrap())
+        .await
+}
+
 pub async fn set_problem(pid: &Pid, problem: &Problem) -> Result<(), sqlx::Error> {
     let pid = pid.0.as_str();
-    let json = serde_json::to_string(&problem).unwrap();
+    let json = serde_json::to_string(&ProblemDetail::new(problem)).unwrap();
     let owner = problem.owner.map(|x| x.0 as i64);
+    let title = problem.title.as_str();
     let db = DB.get().unwrap();
     sqlx::query!(
-        "UPDATE problems SET owner=$2,json=$3 WHERE pid=$1",
+        "UPDATE problems SET owner=$2,title=$3,time_limit=$4,memory_limit=$5,json=$6 WHERE pid=$1",
         pid,
         owner,
+        title,
+        problem.time_limit,
+        problem.memory_limit,
         json
     )
     .execute(db)
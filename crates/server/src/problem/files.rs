@@ -13,13 +13,14 @@ pub async fn can_access_problem_file(
         .ok_or(ServerError::NotFound)?;
     let mut ret = file.is_public;
     if let Some(user) = user {
-        ret |= user.privilege.edit_problems;
+        ret |= user.privilege.has(Permission::EditProblems);
         ret |= Some(user.uid) == problem.owner;
     }
     Ok(ret)
 }
 
 pub async fn clean_unused_problem_files(pid: &Pid) -> Result<u64, ServerError> {
+    let files_lock = problem_files_lock(false).await?;
     let lock = problem_write_lock(pid).await;
     let files = get_problem(pid).await?.files.clone();
     let mut joinset = tokio::task::JoinSet::new();
@@ -53,11 +54,12 @@ pub async fn clean_unused_problem_files(pid: &Pid) -> Result<u64, ServerError> {
         count += 1;
     }
     drop(lock);
+    drop(files_lock);
     Ok(count)
 }
 
 use axum::extract::{Extension, Multipart, Path};
-use shared::user::LoginedUser;
+use shared::user::{LoginedUser, Permission};
 pub async fn commit_problem_files(
     Extension(login): Extension<Option<LoginedUser>>,
     Path(pid): Path<Pid>,
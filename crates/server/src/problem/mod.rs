@@ -9,14 +9,14 @@ use compact_str::CompactString;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 pub use shared::problem::*;
-use shared::user::{LoginedUser, Uid};
+use shared::user::{LoginedUser, Permission, Uid};
 use static_init::dynamic;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
 use tokio_util::io::ReaderStream;
 use uuid::Uuid;
@@ -35,6 +35,10 @@ pub struct Problem {
     pub time_limit: u32,
     pub testcases: Arc<Vec<Testcase>>,
     pub files: Arc<Vec<ProblemFile>>,
+    #[serde(default)]
+    pub run_limits: RunLimits,
+    #[serde(default)]
+    pub file_io: Option<FileIo>,
 }
 
 impl Problem {
@@ -66,6 +70,51 @@ pub async fn set_problem(pid: &Pid, data: Arc<Problem>) -> Result<(), ServerErro
     Ok(())
 }
 
+/// Creates an empty problem owned by `owner`, who can then edit it.
+pub async fn create_problem(owner: Uid, pid: Pid) -> Result<(), ServerError> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if pid.0.is_empty() || pid.0.len() > 32 || !pid.0.chars().all(valid) {
+        return Err(ServerError::BadData);
+    }
+    let _guard = problem_write_lock(&pid).await;
+    match get_problem(&pid).await {
+        Ok(_) => return Err(ServerError::Fuck),
+        Err(ServerError::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+    let problem = Problem {
+        title: pid.0.clone(),
+        pid: pid.clone(),
+        created_time: chrono::Local::now().timestamp(),
+        owner: Some(owner),
+        statement: Default::default(),
+        memory_limit: 256,
+        time_limit: 1000,
+        testcases: Default::default(),
+        files: Default::default(),
+        run_limits: Default::default(),
+        file_io: None,
+    };
+    fs::create_dir_all(problem_storage_path(&pid))
+        .await
+        .map_err(ServerError::into_internal)?;
+    problem
+        .insert_db(crate::db::DB.get().unwrap())
+        .await
+        .map_err(ServerError::into_internal)?;
+    Ok(())
+}
+
+pub async fn all_problems() -> Result<Vec<Problem>, ServerError> {
+    db::all_problems().await.map_err(ServerError::into_internal)
+}
+
+pub async fn owned_problems(owner: Uid) -> Result<Vec<ProblemProfile>, ServerError> {
+    db::owned_problems(owner)
+        .await
+        .map_err(ServerError::into_internal)
+}
+
 pub async fn get_problem_editable(pid: &Pid) -> Result<ProblemEditable, ServerError> {
     let problem = get_problem_front(pid).await?;
     Ok(ProblemEditable {
@@ -90,6 +139,24 @@ pub async fn get_problem_front(pid: &Pid) -> Result<ProblemFront, ServerError> {
         None
     };
 
+    let mut samples = Vec::new();
+    for (case, testcase) in problem.testcases.iter().enumerate() {
+        if !testcase.is_sample {
+            continue;
+        }
+        let input = problem_file_prefix(pid, &testcase.input_file, SAMPLE_LIMIT).await;
+        let output = problem_file_prefix(pid, &testcase.output_file, SAMPLE_LIMIT).await;
+        let (Ok(input), Ok(output)) = (input, output) else {
+            tracing::warn!("files of sample {case} of {pid} are missing");
+            continue;
+        };
+        samples.push(Sample {
+            case,
+            input: String::from_utf8_lossy(&input).into_owned(),
+            output: String::from_utf8_lossy(&output).into_owned(),
+        });
+    }
+
     let front = ProblemFront {
         owner_display,
         title: problem.title.clone(),
@@ -103,12 +170,33 @@ pub async fn get_problem_front(pid: &Pid) -> Result<ProblemFront, ServerError> {
             .filter(|f| f.is_public)
             .map(|f| f.path.clone())
             .collect(),
+        run_limits: problem.run_limits,
+        file_io: problem.file_io.clone(),
+        samples,
         pid: pid.clone(),
     };
 
     Ok(front)
 }
 
+/// Taken shared to remove problem files and exclusively to back them up. Being a
+/// file lock, it also keeps a backup from the command line and the server apart.
+pub async fn problem_files_lock(exclusive: bool) -> Result<std::fs::File, ServerError> {
+    let path = crate::storage_dir().join("files.lock");
+    tokio::task::spawn_blocking(move || -> std::io::Result<_> {
+        let file = std::fs::File::create(path)?;
+        if exclusive {
+            file.lock()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(file)
+    })
+    .await
+    .unwrap()
+    .map_err(ServerError::into_internal)
+}
+
 #[must_use]
 pub async fn problem_read_lock(pid: &Pid) -> OwnedRwLockReadGuard<()> {
     PROBLEM_LOCKS
@@ -137,16 +225,18 @@ pub async fn problem_data(pid: Pid) -> Result<ProblemData, ServerError> {
         testcases: (*problem.testcases).clone(),
         time_limit: problem.time_limit,
         memory_limit: problem.memory_limit,
+        run_limits: problem.run_limits,
+        file_io: problem.file_io.clone(),
     };
     Ok(data)
 }
 
+pub fn problems_storage_path() -> PathBuf {
+    crate::storage_dir().join("problems")
+}
+
 fn problem_storage_path(pid: &Pid) -> PathBuf {
-    dirs::home_dir()
-        .unwrap()
-        .join("mygoj")
-        .join("problems")
-        .join(&pid.0)
+    problems_storage_path().join(&pid.0)
 }
 
 async fn get_problem_file(pid: &Pid, path: &str) -> Result<PathBuf, ServerError> {
@@ -176,8 +266,26 @@ pub async fn send_problem_file(pid: Pid, filename: &str) -> Result<Response, Ser
     Ok(resp)
 }
 
+/// Reads at most `limit` bytes from the start of a problem file.
+pub async fn problem_file_prefix(
+    pid: &Pid,
+    filename: &str,
+    limit: usize,
+) -> Result<Vec<u8>, ServerError> {
+    let path = get_problem_file(pid, filename).await?;
+    let file = fs::File::open(path)
+        .await
+        .map_err(|_| ServerError::NotFound)?;
+    let mut data = Vec::new();
+    file.take(limit as u64)
+        .read_to_end(&mut data)
+        .await
+        .map_err(ServerError::into_internal)?;
+    Ok(data)
+}
+
 pub async fn can_manage_problem(user: &LoginedUser, pid: &Pid) -> Result<bool, ServerError> {
-    if user.privilege.edit_problems {
+    if user.privilege.has(Permission::EditProblems) {
         return Ok(true);
     }
     let p = get_problem(pid).await?;
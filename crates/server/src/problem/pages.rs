@@ -1,7 +1,6 @@
 use super::*;
 use crate::db::DB;
 use dashmap::DashMap;
-use futures_util::StreamExt;
 use sqlx::Row;
 use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
@@ -74,31 +73,18 @@ pub async fn get_page_count() -> Result<u64, ServerError> {
 
 const PAGE_SIZE: u64 = 10;
 
-async fn db_get_problems_page(index: u64) -> Result<Arc<Vec<Problem>>, ServerError> {
-    let db = DB.get().unwrap();
+async fn db_get_problems_page(index: u64) -> Result<Arc<Vec<ProblemProfile>>, ServerError> {
     let offset = (index * PAGE_SIZE) as i64;
     let limit = PAGE_SIZE as i64;
-    let mut stream = sqlx::query!(
-        "SELECT json FROM problems LIMIT $1 OFFSET $2",
-        limit,
-        offset
-    )
-    .fetch(db);
-    let mut ret = Vec::new();
-    while let Some(item) = stream.next().await {
-        let json = item
-            .map_err(ServerError::into_internal)?
-            .json
-            .ok_or(ServerError::BadData)?;
-        let p: Problem = serde_json::from_str(&json).map_err(|_| ServerError::BadData)?;
-        ret.push(p);
-    }
-    Ok(Arc::new(ret))
+    let page = db::problems_page(offset, limit)
+        .await
+        .map_err(ServerError::into_internal)?;
+    Ok(Arc::new(page))
 }
 
 #[dynamic]
-static PROBLEMS_PAGES: DashMap<u64, Arc<TimeCache<Arc<Vec<Problem>>>>> = DashMap::new();
-pub async fn get_problems_page(index: u64) -> Result<Arc<Vec<Problem>>, ServerError> {
+static PROBLEMS_PAGES: DashMap<u64, Arc<TimeCache<Arc<Vec<ProblemProfile>>>>> = DashMap::new();
+pub async fn get_problems_page(index: u64) -> Result<Arc<Vec<ProblemProfile>>, ServerError> {
     let lock = PROBLEMS_PAGES
         .entry(index)
         .or_insert_with(|| Arc::new(TimeCache::new()))
@@ -1,31 +1,241 @@
 use super::*;
 use crate::db::DB;
+use serde::{Deserialize, Serialize};
+use shared::judge::CompileError;
+use sqlx::{QueryBuilder, Row, Sqlite};
+use std::collections::BTreeMap;
+
+/// The judge only compiles C++ so far.
+const LANGUAGE: &str = "cpp";
+
+/// What of a record is kept in its json, everything else has a column,
+/// the results of testcases are in `record_cases`.
+#[derive(Serialize, Deserialize)]
+struct RecordDetail {
+    code: String,
+    compile_error: Option<CompileError>,
+    samples: Vec<usize>,
+    sample_outputs: BTreeMap<usize, String>,
+}
+
+impl RecordDetail {
+    fn new(record: &Record) -> Self {
+        let compile_error = match &record.status {
+            RecordStatus::CompileError(ce) => Some(ce.clone()),
+            _ => None,
+        };
+        RecordDetail {
+            code: record.code.clone(),
+            compile_error,
+            samples: record.samples.clone(),
+            sample_outputs: record.sample_outputs.clone(),
+        }
+    }
+}
 
 pub async fn get_record(rid: Rid) -> Result<Option<Record>, sqlx::Error> {
     let db = DB.get().unwrap();
     let rid = rid.0 as i64;
-    let ret = sqlx::query!("SELECT (json) FROM records WHERE rid=$1", rid)
-        .fetch_optional(db)
-        .await?
-        .map(|rec| serde_json::from_str(&rec.json.unwrap()).unwrap());
-    Ok(ret)
+    let Some(row) = sqlx::query!(
+        "SELECT pid,uid,flag,time,max_time,sum_time,memory_used,json FROM records WHERE rid=$1",
+        rid
+    )
+    .fetch_optional(db)
+    .await?
+    else {
+        return Ok(None);
+    };
+    let detail: RecordDetail = serde_json::from_str(&row.json.unwrap()).unwrap();
+    let flag = RecordFlag::parse(&row.flag).unwrap_or(RecordFlag::Uke);
+    let status = match flag.verdict() {
+        Some(verdict) => RecordStatus::Completed(AllJudgeResult {
+            cases: get_cases(rid).await?,
+            verdict,
+            memory_used: row.memory_used.unwrap_or(0) as u32,
+            max_time: row.max_time.unwrap_or(0) as u32,
+            sum_time: row.sum_time.unwrap_or(0) as u32,
+        }),
+        None if flag == RecordFlag::Ce => RecordStatus::CompileError(detail.compile_error.unwrap()),
+        // a record being judged is stored as waiting
+        None => RecordStatus::Waiting,
+    };
+    Ok(Some(Record {
+        rid: Rid(rid as u64),
+        uid: Uid(row.uid as u64),
+        pid: Pid::new(&row.pid),
+        code: detail.code,
+        status,
+        time: row.time,
+        samples: detail.samples,
+        sample_outputs: detail.sample_outputs,
+    }))
+}
+
+async fn get_cases(rid: i64) -> Result<Vec<SingleJudgeResult>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT verdict,time_used,wall_time,memory_used,termination FROM record_cases WHERE rid=$1 ORDER BY idx",
+        rid
+    )
+    .fetch_all(DB.get().unwrap())
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| SingleJudgeResult {
+            verdict: RecordFlag::parse(&row.verdict)
+                .and_then(|flag| flag.verdict())
+                .unwrap_or(Verdict::Uke),
+            memory_used: row.memory_used as u32,
+            time_used: row.time_used as u32,
+            wall_time: row.wall_time as u32,
+            termination: row
+                .termination
+                .map(|termination| serde_json::from_str(&termination).unwrap()),
+        })
+        .collect())
 }
 
 pub async fn update_record(rid: Rid, record: &Record) -> Result<(), sqlx::Error> {
-    let db = DB.get().unwrap();
     assert!(record.status.done());
-    let json = serde_json::to_string(record).unwrap();
+    let json = serde_json::to_string(&RecordDetail::new(record)).unwrap();
     let rid = rid.0 as i64;
     let flag = record.status.flag().as_str();
+    let (max_time, sum_time, memory_used) = usage(&record.status);
+    let mut txn = DB.get().unwrap().begin_with("BEGIN IMMEDIATE").await?;
     sqlx::query!(
-        "UPDATE records SET json=$1,flag=$2 WHERE rid=$3",
+        "UPDATE records SET json=$1,flag=$2,max_time=$3,sum_time=$4,memory_used=$5 WHERE rid=$6",
         json,
         flag,
+        max_time,
+        sum_time,
+        memory_used,
         rid
     )
-    .execute(db)
+    .execute(&mut *txn)
+    .await?;
+    sqlx::query!("DELETE FROM record_cases WHERE rid=$1", rid)
+        .execute(&mut *txn)
+        .await?;
+    if let RecordStatus::Completed(all) = &record.status {
+        for (idx, case) in all.cases.iter().enumerate() {
+            let idx = idx as i64;
+            let verdict = case.verdict.flag().as_str();
+            let termination = case
+                .termination
+                .as_ref()
+                .map(|termination| serde_json::to_string(termination).unwrap());
+            sqlx::query!(
+                "INSERT INTO record_cases (rid,idx,verdict,time_used,wall_time,memory_used,termination)
+                VALUES ($1,$2,$3,$4,$5,$6,$7)",
+                rid,
+                idx,
+                verdict,
+                case.time_used,
+                case.wall_time,
+                case.memory_used,
+                termination
+            )
+            .execute(&mut *txn)
+            .await?;
+        }
+    }
+    txn.commit().await
+}
+
+/// Puts a record back to waiting if its flag is still `from`, returns whether it was,
+/// so only one of several rejudges of the same record goes through.
+pub async fn reset_record(
+    rid: Rid,
+    record: &Record,
+    from: RecordFlag,
+    priority: JudgePriority,
+) -> Result<bool, sqlx::Error> {
+    assert_eq!(record.status, RecordStatus::Waiting);
+    let json = serde_json::to_string(&RecordDetail::new(record)).unwrap();
+    let (rid, flag, from) = (rid.0 as i64, RecordFlag::Waiting.as_str(), from.as_str());
+    let priority = priority.as_str();
+    let mut txn = DB.get().unwrap().begin_with("BEGIN IMMEDIATE").await?;
+    let res = sqlx::query!(
+        "UPDATE records SET json=$1,flag=$2,max_time=NULL,sum_time=NULL,memory_used=NULL,priority=$3
+        WHERE rid=$4 AND flag=$5",
+        json,
+        flag,
+        priority,
+        rid,
+        from
+    )
+    .execute(&mut *txn)
     .await?;
-    Ok(())
+    if res.rows_affected() != 1 {
+        return Ok(false);
+    }
+    sqlx::query!("DELETE FROM record_cases WHERE rid=$1", rid)
+        .execute(&mut *txn)
+        .await?;
+    txn.commit().await?;
+    Ok(true)
+}
+
+/// `max_time`, `sum_time` and `memory_used` of a judged record.
+fn usage(status: &RecordStatus) -> (Option<u32>, Option<u32>, Option<u32>) {
+    match status {
+        RecordStatus::Completed(all) => (
+            Some(all.max_time),
+            Some(all.sum_time),
+            Some(all.memory_used),
+        ),
+        _ => (None, None, None),
+    }
+}
+
+pub async fn search(query: &RecordQuery, limit: u64) -> Result<Vec<RecordSummary>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "SELECT rid,pid,uid,flag,time,max_time,memory_used FROM records WHERE 1=1",
+    );
+    if let Some(pid) = &query.pid {
+        builder.push(" AND pid=").push_bind(pid.0.to_string());
+    }
+    if let Some(uid) = query.uid {
+        builder.push(" AND uid=").push_bind(uid.0 as i64);
+    }
+    if let Some(flag) = query.flag {
+        builder.push(" AND flag=").push_bind(flag.as_str());
+    }
+    if let Some(since) = query.since {
+        builder.push(" AND time>=").push_bind(since);
+    }
+    let offset = query.index.saturating_mul(limit).min(i64::MAX as u64) as i64;
+    builder
+        .push(" ORDER BY rid DESC LIMIT ")
+        .push_bind(limit as i64)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let rows = builder.build().fetch_all(DB.get().unwrap()).await?;
+    rows.iter()
+        .map(|row| {
+            let flag: String = row.try_get("flag")?;
+            Ok(RecordSummary {
+                rid: Rid(row.try_get::<i64, _>("rid")? as u64),
+                pid: Pid::new(row.try_get("pid")?),
+                uid: Uid(row.try_get::<i64, _>("uid")? as u64),
+                flag: RecordFlag::parse(&flag).unwrap_or(RecordFlag::Uke),
+                time: row.try_get("time")?,
+                max_time: row.try_get("max_time")?,
+                memory_used: row.try_get("memory_used")?,
+            })
+        })
+        .collect()
+}
+
+pub async fn problem_records(pid: &Pid) -> Result<Vec<Rid>, sqlx::Error> {
+    let db = DB.get().unwrap();
+    let pid = pid.0.as_str();
+    let ret = sqlx::query!("SELECT rid FROM records WHERE pid=$1", pid)
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|rec| Rid(rec.rid.unwrap() as u64))
+        .collect();
+    Ok(ret)
 }
 
 pub async fn submit(uid: Uid, Submission { code, pid }: Submission) -> Result<Record, sqlx::Error> {
@@ -33,12 +243,15 @@ pub async fn submit(uid: Uid, Submission { code, pid }: Submission) -> Result<Re
     let time = chrono::Utc::now().timestamp();
     let res = {
         let (pid, uid, flag) = (pid.0.as_str(), uid.0 as i64, RecordFlag::Waiting.as_str());
+        let priority = JudgePriority::Submission.as_str();
         sqlx::query!(
-            "INSERT INTO records (pid,uid,flag,time) VALUES ($1,$2,$3,$4)",
+            "INSERT INTO records (pid,uid,flag,time,priority,language) VALUES ($1,$2,$3,$4,$5,$6)",
             pid,
             uid,
             flag,
-            time
+            time,
+            priority,
+            LANGUAGE
         )
         .execute(db)
         .await?
@@ -52,11 +265,64 @@ pub async fn submit(uid: Uid, Submission { code, pid }: Submission) -> Result<Re
         code,
         time,
         status: RecordStatus::Waiting,
+        samples: Vec::new(),
+        sample_outputs: Default::default(),
     };
-    let json = serde_json::to_string(&record).unwrap();
+    let json = serde_json::to_string(&RecordDetail::new(&record)).unwrap();
     let rid = rid.0 as i64;
     sqlx::query!("UPDATE records SET json=$1 WHERE rid=$2", json, rid)
         .execute(db)
         .await?;
     Ok(record)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrent_rejudges_reset_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.db");
+        std::fs::File::create(&path).unwrap();
+        crate::db::database_connect(path.to_str().unwrap())
+            .await
+            .unwrap();
+        crate::migrate::migrate().await.unwrap();
+
+        let submission = Submission {
+            code: String::new(),
+            pid: Pid::new("1"),
+        };
+        let mut record = submit(Uid(1), submission).await.unwrap();
+        let rid = record.rid;
+        let case = SingleJudgeResult {
+            verdict: Verdict::Ac,
+            memory_used: 1024,
+            time_used: 10,
+            wall_time: 12,
+            termination: Some(shared::judge::Termination::ExitCode(0)),
+        };
+        record.status = RecordStatus::Completed(AllJudgeResult {
+            cases: vec![case.clone(), case],
+            verdict: Verdict::Ac,
+            memory_used: 1024,
+            max_time: 10,
+            sum_time: 20,
+        });
+        update_record(rid, &record).await.unwrap();
+        assert_eq!(get_record(rid).await.unwrap().unwrap(), record);
+
+        record.status = RecordStatus::Waiting;
+        let rejudge = || reset_record(rid, &record, RecordFlag::Ac, JudgePriority::Rejudge);
+        let (a, b) = tokio::join!(rejudge(), rejudge());
+        assert!(a.unwrap() ^ b.unwrap());
+        let stored = get_record(rid).await.unwrap().unwrap();
+        assert_eq!(stored.status, RecordStatus::Waiting);
+        let cases = sqlx::query!("SELECT COUNT(*) AS count FROM record_cases")
+            .fetch_one(DB.get().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(cases.count, 0);
+    }
+}
@@ -1,11 +1,18 @@
 mod cache;
 mod db;
+mod output;
 
-use super::ServerError;
-use super::judge::JUDGE_QUEUE;
+pub use output::{forget_case_outputs, get_case_output, receive_case_output};
+
+use super::judge::{enqueue, enqueue_front};
 use super::problem::{get_problem, problem_read_lock};
-use dashmap::DashMap;
-use shared::judge::SingleJudgeResult;
+use super::user::{forget_profile, rank_accepted, rank_submitted};
+use super::{Fuck, ServerError};
+use dashmap::{DashMap, Entry};
+use shared::judge::{
+    AllJudgeResult, JudgePriority, JudgeTask, QueuedTask, SingleJudgeResult, Verdict,
+};
+use shared::problem::Pid;
 use shared::record::*;
 use shared::submission::Submission;
 use shared::user::Uid;
@@ -35,27 +42,42 @@ struct JudgingRecord {
     record: Record,
     channel: RecordChannel,
     problem_lock: OwnedRwLockReadGuard<()>,
+    priority: JudgePriority,
+    attempts: u32,
 }
 
+const MAX_JUDGE_ATTEMPTS: u32 = 3;
+
 #[dynamic]
 static JUDGING_RECORDS: DashMap<Rid, JudgingRecord> = DashMap::new();
 
-pub async fn new_record(rid: Rid, record: Record) -> Result<(), ServerError> {
+pub async fn new_record(
+    rid: Rid,
+    record: Record,
+    priority: JudgePriority,
+) -> Result<(), ServerError> {
     let pid = &record.pid;
+    let uid = record.uid;
     let case_count = get_problem(pid).await?.testcases.len();
     let lock = problem_read_lock(pid).await;
-    JUDGING_RECORDS.insert(
-        rid,
-        JudgingRecord {
-            record,
-            channel: RecordChannel::new(case_count * 2),
-            problem_lock: lock,
-        },
-    );
-    {
-        let mut queue = JUDGE_QUEUE.lock().await;
-        queue.push_back(rid);
+    match JUDGING_RECORDS.entry(rid) {
+        Entry::Occupied(_) => return Err(ServerError::Fuck),
+        Entry::Vacant(e) => {
+            e.insert(JudgingRecord {
+                record,
+                channel: RecordChannel::new(case_count * 2),
+                problem_lock: lock,
+                priority,
+                attempts: 0,
+            });
+        }
     }
+    enqueue(QueuedTask {
+        task: JudgeTask::Record(rid),
+        uid,
+        priority,
+    })
+    .await;
     Ok(())
 }
 
@@ -70,10 +92,60 @@ pub async fn submit(uid: Uid, submission: Submission) -> Result<Rid, ServerError
         .map_err(ServerError::into_internal)?;
     let rid = record.rid;
     cache::new_record(record.clone()).await;
-    new_record(rid, record).await?;
+    new_record(rid, record, JudgePriority::Submission).await?;
+    forget_profile(uid).await;
+    rank_submitted(uid).await;
     Ok(rid)
 }
 
+pub async fn rejudge(rid: Rid) -> Result<(), ServerError> {
+    let mut record = get_record(rid).await?;
+    if !record.status.done() {
+        return Err(ServerError::Fuck);
+    }
+    let from = record.flag();
+    record.status = RecordStatus::Waiting;
+    record.samples.clear();
+    record.sample_outputs.clear();
+    let reset = db::reset_record(rid, &record, from, JudgePriority::Rejudge)
+        .await
+        .map_err(ServerError::into_internal)?;
+    // another rejudge got there first
+    if !reset {
+        return Err(ServerError::Fuck);
+    }
+    if from == RecordFlag::Ac {
+        rank_accepted(record.uid, &record.pid, false).await;
+    }
+    cache::update_record(rid, record.clone()).await;
+    forget_profile(record.uid).await;
+    new_record(rid, record, JudgePriority::Rejudge).await
+}
+
+/// Rejudges every finished record of a problem, returning how many were queued.
+pub async fn rejudge_problem(pid: &Pid) -> Result<u64, ServerError> {
+    let rids = db::problem_records(pid)
+        .await
+        .map_err(ServerError::into_internal)?;
+    let mut count = 0;
+    for rid in rids {
+        if get_record(rid).await?.status.done() {
+            rejudge(rid).await?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+pub async fn search_records(
+    query: &RecordQuery,
+    limit: u64,
+) -> Result<Vec<RecordSummary>, ServerError> {
+    db::search(query, limit)
+        .await
+        .map_err(ServerError::into_internal)
+}
+
 pub async fn get_record(rid: Rid) -> Result<Record, ServerError> {
     if let Some(rec) = cache::get_record(rid).await {
         return Ok(rec);
@@ -88,20 +160,28 @@ pub async fn update_record_single(
     rid: Rid,
     idx: usize,
     res: SingleJudgeResult,
+    sample_output: Option<String>,
 ) -> Result<(), ServerError> {
-    let mut record = JUDGING_RECORDS.get_mut(&rid).unwrap();
+    let mut record = JUDGING_RECORDS.get_mut(&rid).ok_or(ServerError::NotFound)?;
     let JudgingRecord {
-        record,
-        channel,
-        problem_lock: _,
+        record, channel, ..
     } = &mut *record;
     let RecordStatus::Running(status) = &mut record.status else {
-        unreachable!()
+        return Err(ServerError::Fuck);
     };
 
-    let single = status.get_mut(idx).unwrap();
-    assert!(single.is_none());
+    let single = status.get_mut(idx).fuck()?;
+    if single.is_some() {
+        return Err(ServerError::Fuck);
+    }
     *single = Some(res.clone());
+    // only sent for the testcases the judge took as samples
+    if let Some(output) = sample_output {
+        if let Err(pos) = record.samples.binary_search(&idx) {
+            record.samples.insert(pos, idx);
+        }
+        record.sample_outputs.insert(idx, output);
+    }
 
     cache::update_record(rid, record.clone()).await;
 
@@ -123,7 +203,7 @@ pub async fn update_record(rid: Rid, status: RecordStatus) -> Result<(), ServerE
             RecordStatus::CompileError(ce) => RecordMessage::CompileError(ce),
             RecordStatus::Running(v) => RecordMessage::Compiled(v.len()),
             RecordStatus::Completed(all) => RecordMessage::Completed(all),
-            RecordStatus::Waiting => unreachable!(),
+            RecordStatus::Waiting => RecordMessage::Waiting,
         };
         sender.send(msg).unwrap();
     };
@@ -135,8 +215,9 @@ pub async fn update_record(rid: Rid, status: RecordStatus) -> Result<(), ServerE
                 mut record,
                 channel,
                 problem_lock,
+                ..
             },
-        ) = JUDGING_RECORDS.remove(&rid).unwrap();
+        ) = JUDGING_RECORDS.remove(&rid).ok_or(ServerError::NotFound)?;
         drop(problem_lock);
         record.status = status.clone();
 
@@ -144,10 +225,14 @@ pub async fn update_record(rid: Rid, status: RecordStatus) -> Result<(), ServerE
         db::update_record(rid, &record)
             .await
             .map_err(ServerError::into_internal)?;
+        forget_profile(record.uid).await;
+        if record.flag() == RecordFlag::Ac {
+            rank_accepted(record.uid, &record.pid, true).await;
+        }
 
         send(&channel.tx);
     } else {
-        let mut record = JUDGING_RECORDS.get_mut(&rid).unwrap();
+        let mut record = JUDGING_RECORDS.get_mut(&rid).ok_or(ServerError::NotFound)?;
         record.record.status = status.clone();
         cache::update_record(rid, record.record.clone()).await;
 
@@ -158,6 +243,41 @@ pub async fn update_record(rid: Rid, status: RecordStatus) -> Result<(), ServerE
     Ok(())
 }
 
+/// Puts a record whose judge machine got lost back into the queue,
+/// giving up with an unknown error once it has failed too many times.
+/// A record the machine never received is not counted as `failed`.
+pub async fn requeue_record(rid: Rid, failed: bool) -> Result<(), ServerError> {
+    let (attempts, queued) = {
+        let Some(mut judging) = JUDGING_RECORDS.get_mut(&rid) else {
+            return Ok(());
+        };
+        if failed {
+            judging.attempts += 1;
+        }
+        let queued = QueuedTask {
+            task: JudgeTask::Record(rid),
+            uid: judging.record.uid,
+            priority: judging.priority,
+        };
+        (judging.attempts, queued)
+    };
+    if attempts >= MAX_JUDGE_ATTEMPTS {
+        tracing::warn!("give up judging {rid} after {attempts} attempts");
+        let res = AllJudgeResult {
+            cases: Vec::new(),
+            verdict: Verdict::Uke,
+            memory_used: 0,
+            max_time: 0,
+            sum_time: 0,
+        };
+        return update_record(rid, RecordStatus::Completed(res)).await;
+    }
+    tracing::info!("requeue {rid} attempt {attempts}");
+    update_record(rid, RecordStatus::Waiting).await?;
+    enqueue_front(queued).await;
+    Ok(())
+}
+
 #[derive(serde::Deserialize)]
 pub struct Qrid {
     rid: u64,
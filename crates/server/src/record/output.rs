@@ -0,0 +1,132 @@
+//! Outputs of failed testcases for the managers of a problem, and of sample testcases for
+//! the submitter as well. Sample outputs are uploaded with their results and stored with
+//! the record. Judge machines keep the others for a while and upload one only when it is
+//! asked for, the server keeps it shortly after.
+
+use super::*;
+use crate::judge::request_case_output;
+use crate::problem::{can_manage_problem, problem_file_prefix};
+use shared::problem::SAMPLE_LIMIT;
+use shared::user::LoginedUser;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+struct CaseOutput {
+    status: CaseOutputStatus,
+    /// When it was requested or received, in ms.
+    updated: u64,
+}
+
+#[dynamic]
+static CASE_OUTPUTS: Mutex<HashMap<(Rid, usize), CaseOutput>> = Mutex::new(HashMap::new());
+
+/// How long an uploaded output is kept before asking the judge again.
+const OUTPUT_EXPIRE: u64 = 10 * 60 * 1000;
+/// How long to wait for the judge machine to upload an output.
+const UPLOAD_TIMEOUT: u64 = 10_000;
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// Returns the output of a testcase, asking the judge machine for it the first time.
+pub async fn get_case_output(
+    user: &LoginedUser,
+    rid: Rid,
+    idx: usize,
+) -> Result<CaseOutputStatus, ServerError> {
+    let record = get_record(rid).await?;
+    let RecordStatus::Completed(res) = &record.status else {
+        return Err(ServerError::Fuck);
+    };
+    let case = res.cases.get(idx).ok_or(ServerError::NotFound)?;
+    let sample = record.samples.contains(&idx);
+    let manage = can_manage_problem(user, &record.pid).await?;
+    let allowed = if sample {
+        manage || record.uid == user.uid
+    } else {
+        manage && case.verdict != Verdict::Ac
+    };
+    if !allowed {
+        return Err(ServerError::NoPrivilege);
+    }
+    if sample {
+        return match record.sample_outputs.get(&idx) {
+            Some(output) => Ok(CaseOutputStatus::Ready(
+                case_diff(&record.pid, idx, output.clone(), true).await?,
+            )),
+            // judged before sample outputs were stored
+            None => Ok(CaseOutputStatus::Unavailable),
+        };
+    }
+
+    let now = now();
+    let mut outputs = CASE_OUTPUTS.lock().await;
+    outputs.retain(|_, output| now.saturating_sub(output.updated) < OUTPUT_EXPIRE);
+    if let Some(output) = outputs.get_mut(&(rid, idx)) {
+        if output.status == CaseOutputStatus::Requested
+            && now.saturating_sub(output.updated) > UPLOAD_TIMEOUT
+        {
+            output.status = CaseOutputStatus::Unavailable;
+        }
+        return Ok(output.status.clone());
+    }
+    let status = match request_case_output(rid, idx).await {
+        Ok(()) => CaseOutputStatus::Requested,
+        Err(_) => CaseOutputStatus::Unavailable,
+    };
+    let output = CaseOutput {
+        status: status.clone(),
+        updated: now,
+    };
+    outputs.insert((rid, idx), output);
+    Ok(status)
+}
+
+pub async fn receive_case_output(
+    rid: Rid,
+    idx: usize,
+    output: Option<String>,
+) -> Result<(), ServerError> {
+    let status = match output {
+        Some(output) => {
+            let pid = get_record(rid).await?.pid;
+            CaseOutputStatus::Ready(case_diff(&pid, idx, output, false).await?)
+        }
+        None => CaseOutputStatus::Unavailable,
+    };
+    let mut outputs = CASE_OUTPUTS.lock().await;
+    let output = outputs.get_mut(&(rid, idx)).fuck()?;
+    output.status = status;
+    output.updated = now();
+    Ok(())
+}
+
+/// Compares an output with the beginning of the answer, sample testcases show their input too.
+async fn case_diff(
+    pid: &Pid,
+    idx: usize,
+    output: String,
+    sample: bool,
+) -> Result<CaseDiff, ServerError> {
+    let problem = get_problem(pid).await?;
+    let case = problem.testcases.get(idx).fuck()?;
+    let limit = if sample {
+        SAMPLE_LIMIT
+    } else {
+        CASE_OUTPUT_LIMIT
+    };
+    let answer = problem_file_prefix(pid, &case.output_file, limit).await?;
+    let answer = String::from_utf8_lossy(&answer).into_owned();
+    let mut diff = CaseDiff::new(output, answer);
+    if sample {
+        let input = problem_file_prefix(pid, &case.input_file, limit).await?;
+        diff.input = Some(String::from_utf8_lossy(&input).into_owned());
+    }
+    Ok(diff)
+}
+
+/// Drops the outputs of a record that is being judged again.
+pub async fn forget_case_outputs(rid: Rid) {
+    CASE_OUTPUTS.lock().await.retain(|key, _| key.0 != rid);
+}
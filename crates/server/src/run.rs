@@ -0,0 +1,137 @@
+//! Code run against custom input without submitting. Runs only live in memory
+//! and are forgotten a while after they finish.
+
+use super::judge::{enqueue, enqueue_front};
+use super::problem::get_problem;
+use super::{Fuck, ServerError};
+use shared::judge::*;
+use shared::user::Uid;
+use static_init::dynamic;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+
+struct Run {
+    uid: Uid,
+    run: CustomRun,
+    status: CustomRunStatus,
+    /// When it was started or finished, in ms.
+    updated: u64,
+    attempts: u32,
+}
+
+#[dynamic]
+static RUNS: Mutex<HashMap<RunId, Run>> = Mutex::new(HashMap::new());
+
+static NEXT_RUN: AtomicU64 = AtomicU64::new(0);
+
+const STDIN_LIMIT: usize = 1 << 20;
+/// How long a finished run is kept for its owner to fetch.
+const RUN_EXPIRE: u64 = 10 * 60 * 1000;
+const MAX_RUN_ATTEMPTS: u32 = 3;
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// Queues a run, a user may only have one run unfinished at a time.
+pub async fn start_run(uid: Uid, run: CustomRun) -> Result<RunId, ServerError> {
+    if run.code.len() > (50 << 10) || run.stdin.len() > STDIN_LIMIT {
+        return Err(ServerError::Fuck);
+    }
+    get_problem(&run.pid).await?;
+
+    let id = RunId(NEXT_RUN.fetch_add(1, Ordering::Relaxed));
+    {
+        let mut runs = RUNS.lock().await;
+        let now = now();
+        runs.retain(|_, run| !run.status.done() || now.saturating_sub(run.updated) < RUN_EXPIRE);
+        if runs
+            .values()
+            .any(|run| run.uid == uid && !run.status.done())
+        {
+            return Err(ServerError::Fuck);
+        }
+        runs.insert(
+            id,
+            Run {
+                uid,
+                run,
+                status: CustomRunStatus::Waiting,
+                updated: now,
+                attempts: 0,
+            },
+        );
+    }
+    enqueue(QueuedTask {
+        task: JudgeTask::Run(id),
+        uid,
+        priority: JudgePriority::Run,
+    })
+    .await;
+    Ok(id)
+}
+
+pub async fn get_run_status(uid: Uid, id: RunId) -> Result<CustomRunStatus, ServerError> {
+    let runs = RUNS.lock().await;
+    let run = runs.get(&id).ok_or(ServerError::NotFound)?;
+    if run.uid != uid {
+        return Err(ServerError::NoPrivilege);
+    }
+    Ok(run.status.clone())
+}
+
+pub async fn get_run(id: RunId) -> Result<CustomRun, ServerError> {
+    let runs = RUNS.lock().await;
+    Ok(runs.get(&id).ok_or(ServerError::NotFound)?.run.clone())
+}
+
+pub async fn update_run(id: RunId, status: CustomRunStatus) -> Result<(), ServerError> {
+    let mut runs = RUNS.lock().await;
+    let run = runs.get_mut(&id).fuck()?;
+    if run.status.done() {
+        return Err(ServerError::Fuck);
+    }
+    run.status = status;
+    run.updated = now();
+    Ok(())
+}
+
+/// Puts a run whose judge machine got lost back into the queue,
+/// a run the machine never received is not counted as `failed`.
+pub async fn requeue_run(id: RunId, failed: bool) -> Result<(), ServerError> {
+    let uid = {
+        let mut runs = RUNS.lock().await;
+        let Some(run) = runs.get_mut(&id) else {
+            return Ok(());
+        };
+        if failed {
+            run.attempts += 1;
+        }
+        if run.attempts >= MAX_RUN_ATTEMPTS {
+            tracing::warn!("give up run {id} after {} attempts", run.attempts);
+            run.status = CustomRunStatus::Finished(CustomRunOutput {
+                result: SingleJudgeResult {
+                    verdict: Verdict::Uke,
+                    memory_used: 0,
+                    time_used: 0,
+                    wall_time: 0,
+                    termination: None,
+                },
+                stdout: String::new(),
+                stderr: String::new(),
+            });
+            run.updated = now();
+            return Ok(());
+        }
+        run.status = CustomRunStatus::Waiting;
+        run.uid
+    };
+    enqueue_front(QueuedTask {
+        task: JudgeTask::Run(id),
+        uid,
+        priority: JudgePriority::Run,
+    })
+    .await;
+    Ok(())
+}
@@ -12,7 +12,9 @@ pub async fn startup() {
     let path = storage_dir().join("data.db");
     let path = path.as_os_str().to_str().unwrap();
     db::database_connect(path).await.unwrap();
+    migrate::migrate().await.unwrap();
     judge::init_queue().await.unwrap();
+    user::init_ranklist().await.unwrap();
     tokio::spawn(judge::track_judge_machines());
 }
 
@@ -65,6 +67,7 @@ pub fn router() -> Router {
 
     let api = Router::new()
         .route("/judge", any(judge::receive_message))
+        .route("/judge_ws", any(judge::ws))
         .nest("/front", front_api)
         .layer(trace)
         .layer(cors);
@@ -0,0 +1,3 @@
+-- The judge queue priority of a waiting record, so a restart queues it the same way again.
+
+ALTER TABLE records ADD COLUMN priority TEXT;
@@ -0,0 +1,28 @@
+-- Fields lists and searches read get their own columns, filled from the json.
+-- The json still holds the whole row and is read when a single one is needed.
+
+ALTER TABLE problems ADD COLUMN title TEXT NOT NULL DEFAULT '';
+ALTER TABLE problems ADD COLUMN time_limit INT NOT NULL DEFAULT 0;
+ALTER TABLE problems ADD COLUMN memory_limit INT NOT NULL DEFAULT 0;
+
+UPDATE problems SET
+    title=json_extract(json,'$.title'),
+    time_limit=json_extract(json,'$.time_limit'),
+    memory_limit=json_extract(json,'$.memory_limit')
+WHERE json IS NOT NULL;
+
+CREATE INDEX idx_problems_title ON problems(title);
+
+-- only set once a record is judged without a compile error
+ALTER TABLE records ADD COLUMN max_time INT;
+ALTER TABLE records ADD COLUMN sum_time INT;
+ALTER TABLE records ADD COLUMN memory_used INT;
+
+UPDATE records SET
+    max_time=json_extract(json,'$.status.Completed.max_time'),
+    sum_time=json_extract(json,'$.status.Completed.sum_time'),
+    memory_used=json_extract(json,'$.status.Completed.memory_used')
+WHERE json IS NOT NULL;
+
+CREATE INDEX idx_records_pid_flag_time ON records(pid,flag,time);
+CREATE INDEX idx_records_uid_time ON records(uid,time);
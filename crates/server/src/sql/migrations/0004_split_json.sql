@@ -0,0 +1,54 @@
+-- The json of problems and records keeps only what no query looks at, every
+-- field with a column is read from the column. Results of testcases get a table.
+
+CREATE TABLE record_cases(
+    rid INT NOT NULL,
+    idx INT NOT NULL,
+    -- named like the flag of records
+    verdict TEXT NOT NULL,
+    time_used INT NOT NULL,
+    wall_time INT NOT NULL,
+    memory_used INT NOT NULL,
+    -- json of how the program ended, only for runtime errors
+    termination TEXT,
+    PRIMARY KEY(rid,idx)
+);
+
+CREATE INDEX idx_record_cases_verdict ON record_cases(verdict);
+
+INSERT INTO record_cases (rid,idx,verdict,time_used,wall_time,memory_used,termination)
+SELECT
+    records.rid,
+    CAST(cases.key AS INT),
+    CASE json_extract(cases.value,'$.verdict')
+        WHEN 'Ac' THEN 'AC'
+        WHEN 'Wa' THEN 'WA'
+        WHEN 'Re' THEN 'RE'
+        WHEN 'Tle' THEN 'TLE'
+        WHEN 'Mle' THEN 'MLE'
+        WHEN 'Ole' THEN 'OLE'
+        ELSE 'Unknown Error'
+    END,
+    json_extract(cases.value,'$.time_used'),
+    COALESCE(json_extract(cases.value,'$.wall_time'),0),
+    json_extract(cases.value,'$.memory_used'),
+    json_extract(cases.value,'$.termination')
+FROM records, json_each(records.json,'$.status.Completed.cases') AS cases
+WHERE records.json IS NOT NULL;
+
+-- only C++ is judged so far
+ALTER TABLE records ADD COLUMN language TEXT NOT NULL DEFAULT 'cpp';
+
+CREATE INDEX idx_records_language ON records(language);
+
+UPDATE records SET json=json_object(
+    'code',json_extract(json,'$.code'),
+    'compile_error',json_extract(json,'$.status.CompileError'),
+    'samples',json(COALESCE(json_extract(json,'$.samples'),'[]')),
+    'sample_outputs',json(COALESCE(json_extract(json,'$.sample_outputs'),'{}'))
+)
+WHERE json IS NOT NULL;
+
+UPDATE problems SET json=json_remove(json,
+    '$.pid','$.created_time','$.owner','$.title','$.time_limit','$.memory_limit')
+WHERE json IS NOT NULL;
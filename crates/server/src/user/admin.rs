@@ -0,0 +1,53 @@
+use super::*;
+
+const PAGE_SIZE: u64 = 50;
+
+fn summary(user: User) -> UserSummary {
+    UserSummary {
+        uid: user.uid,
+        username: user.username,
+        nickname: user.nickname,
+        email: user.email,
+        privilege: user.privilege,
+        created_time: user.created_time,
+    }
+}
+
+pub async fn list_users(query: UserQuery) -> Result<UserList, ServerError> {
+    let UserQuery { search, index } = query;
+    if search.len() > 50 {
+        return Err(ServerError::Fuck);
+    }
+    let count = db::count_users(&search)
+        .await
+        .map_err(ServerError::into_internal)?;
+    let offset = index.saturating_mul(PAGE_SIZE).min(i64::MAX as u64) as i64;
+    let users = db::search_users(&search, offset, PAGE_SIZE as i64)
+        .await
+        .map_err(ServerError::into_internal)?;
+    Ok(UserList {
+        users: users.into_iter().map(summary).collect(),
+        page_count: count.div_ceil(PAGE_SIZE),
+    })
+}
+
+/// Drops every token of the user, so they have to log in again.
+pub async fn force_logout(uid: Uid) -> Result<(), ServerError> {
+    get_user(uid).await?.ok_or(ServerError::NotFound)?;
+    cache::remove_tokens_of(uid).await;
+    db::remove_tokens_of(uid)
+        .await
+        .map_err(ServerError::into_internal)
+}
+
+pub async fn reset_password(uid: Uid, password: CompactString) -> Result<(), ServerError> {
+    if password.is_empty() || password.len() > 50 {
+        return Err(ServerError::Fuck);
+    }
+    update_user(uid, |user| {
+        user.password = password;
+        Ok(())
+    })
+    .await?;
+    force_logout(uid).await
+}
@@ -14,13 +14,14 @@ static USERNAMES: HashMap<CompactString, Uid> = HashMap::new();
 static TOKENS: HashMap<Token, Option<Uid>> = HashMap::new();
 
 pub async fn set_user(uid: Uid, user: User) {
-    EMAILS.pin().insert(user.email.clone(), uid);
-    USERNAMES.pin().insert(user.username.clone(), uid);
-    USERS.pin().update(uid, |old| {
+    let users = USERS.pin();
+    if let Some(old) = users.get(&uid) {
         EMAILS.pin().remove(&old.email);
         USERNAMES.pin().remove(&old.username);
-        user.clone()
-    });
+    }
+    EMAILS.pin().insert(user.email.clone(), uid);
+    USERNAMES.pin().insert(user.username.clone(), uid);
+    users.insert(uid, user);
 }
 
 pub async fn add_token(token: Token, uid: Uid) {
@@ -31,6 +32,15 @@ pub async fn remove_token(token: Token) {
     TOKENS.pin().insert(token, None);
 }
 
+pub async fn remove_tokens_of(uid: Uid) {
+    let tokens = TOKENS.pin();
+    for (token, owner) in tokens.iter() {
+        if *owner == Some(uid) {
+            tokens.insert(*token, None);
+        }
+    }
+}
+
 pub async fn get_user(uid: Uid) -> Option<User> {
     USERS.pin().get(&uid).cloned()
 }
@@ -38,6 +38,50 @@ pub async fn remove_token(
     Ok(())
 }
 
+pub async fn remove_tokens_of(uid: Uid) -> Result<(), sqlx::Error> {
+    let uid = uid.0 as i64;
+    sqlx::query!("DELETE FROM tokens WHERE uid=$1", uid)
+        .execute(db::DB.get().unwrap())
+        .await?;
+    Ok(())
+}
+
+pub async fn all_users() -> Result<Vec<User>, sqlx::Error> {
+    let rows = sqlx::query!("SELECT json FROM users ORDER BY uid")
+        .fetch_all(db::DB.get().unwrap())
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| serde_json::from_str(&row.json.unwrap()).unwrap())
+        .collect())
+}
+
+/// Users whose username or email contains `search`, ordered by uid.
+pub async fn search_users(search: &str, offset: i64, limit: i64) -> Result<Vec<User>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT json FROM users WHERE instr(username,$1)>0 OR instr(email,$1)>0 ORDER BY uid LIMIT $2 OFFSET $3",
+        search,
+        limit,
+        offset
+    )
+    .fetch_all(db::DB.get().unwrap())
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| serde_json::from_str(&row.json.unwrap()).unwrap())
+        .collect())
+}
+
+pub async fn count_users(search: &str) -> Result<u64, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!: i64" FROM users WHERE instr(username,$1)>0 OR instr(email,$1)>0"#,
+        search
+    )
+    .fetch_one(db::DB.get().unwrap())
+    .await?;
+    Ok(row.count as u64)
+}
+
 pub async fn get_user(
     con: impl Into<Option<&mut SqliteConnection>>,
     uid: Uid,
@@ -136,6 +180,37 @@ pub async fn find_by_token(
     }
 }
 
+pub async fn change_email(
+    uid: Uid,
+    email: CompactString,
+) -> Result<(), Either<sqlx::Error, ServerError>> {
+    db::transaction(|txn| {
+        Box::pin(async move {
+            if let Some(other) = find_by_email(&mut **txn, &email)
+                .await
+                .map_err(Either::Left)?
+            {
+                return if other == uid {
+                    Ok(())
+                } else {
+                    Err(Either::Right(ServerError::EmailExist))
+                };
+            }
+            let mut user = get_user(&mut **txn, uid)
+                .await
+                .map_err(Either::Left)?
+                .ok_or(Either::Right(ServerError::NotFound))?;
+            user.email = email;
+            set_user(&mut **txn, uid, &user)
+                .await
+                .map_err(Either::Left)?;
+            cache::set_user(uid, user).await;
+            Ok(())
+        })
+    })
+    .await
+}
+
 pub async fn register(
     UserRegistration {
         email,
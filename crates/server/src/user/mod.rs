@@ -1,5 +1,14 @@
+mod admin;
 mod cache;
 mod db;
+mod profile;
+mod rank;
+
+pub use admin::{force_logout, list_users, reset_password};
+pub use profile::{forget_profile, get_user_profile};
+pub use rank::{
+    get_ranklist_page, get_ranklist_page_count, init_ranklist, rank_accepted, rank_submitted,
+};
 
 use super::ServerError;
 use compact_str::CompactString;
@@ -104,7 +113,10 @@ async fn find_by_email(email: &str) -> Result<Option<Uid>, ServerError> {
 }
 
 async fn find_by_username(username: &str) -> Result<Option<Uid>, ServerError> {
-    assert!(shared::is_lowercase(username));
+    // usernames are registered in lowercase, so nobody has this one
+    if !shared::is_lowercase(username) {
+        return Ok(None);
+    }
     if let Some(uid) = cache::find_by_username(username).await {
         return Ok(Some(uid));
     }
@@ -122,16 +134,20 @@ async fn find_by_token(token: Token) -> Result<Option<Uid>, ServerError> {
         .map_err(ServerError::into_internal)
 }
 
+/// Looks a user up by email if `ident` has an `@`, by username otherwise.
+pub async fn find_user(ident: &str) -> Result<Option<Uid>, ServerError> {
+    if ident.contains("@") {
+        find_by_email(ident).await
+    } else {
+        find_by_username(ident).await
+    }
+}
+
 pub async fn user_login(
     ident: CompactString,
     password: CompactString,
 ) -> Result<(Token, LoginedUser), ServerError> {
-    let uid = if ident.contains("@") {
-        find_by_email(&ident).await?
-    } else {
-        find_by_username(&ident).await?
-    }
-    .ok_or(ServerError::UserNotFound)?;
+    let uid = find_user(&ident).await?.ok_or(ServerError::UserNotFound)?;
     let user = get_user(uid).await?.unwrap();
     if user.password != password {
         return Err(ServerError::PasswordWrong);
@@ -151,20 +167,76 @@ pub async fn get_user_login(token: Token) -> Result<LoginedUser, ServerError> {
         .await?
         .ok_or(ServerError::LoginOutDated)?;
     let user = get_user(uid).await?.unwrap();
-    if !user.privilege.enter_site {
+    if !user.privilege.has(Permission::EnterSite) {
         return Err(ServerError::NoPrivilege);
     }
     Ok(user.logined_user())
 }
 
-pub async fn set_su(uid: Uid) -> Result<(), ServerError> {
+pub async fn grant_role(uid: Uid, role: Role) -> Result<(), ServerError> {
+    update_user(uid, move |user| {
+        user.privilege.grant(role);
+        Ok(())
+    })
+    .await
+}
+
+pub async fn revoke_role(uid: Uid, role: Role) -> Result<(), ServerError> {
+    update_user(uid, move |user| {
+        user.privilege.revoke(role);
+        Ok(())
+    })
+    .await
+}
+
+pub async fn change_nickname(uid: Uid, nickname: CompactString) -> Result<(), ServerError> {
+    if nickname.is_empty() || nickname.len() > 50 {
+        return Err(ServerError::Fuck);
+    }
     update_user(uid, |user| {
-        user.privilege = Privilege::ALL;
+        user.nickname = nickname;
+        Ok(())
+    })
+    .await?;
+    forget_profile(uid).await;
+    Ok(())
+}
+
+pub async fn change_password(uid: Uid, change: PasswordChange) -> Result<(), ServerError> {
+    let PasswordChange { old, new } = change;
+    if new.len() > 50 {
+        return Err(ServerError::Fuck);
+    }
+    update_user(uid, move |user| {
+        if user.password != old {
+            return Err(ServerError::PasswordWrong);
+        }
+        user.password = new;
         Ok(())
     })
     .await
 }
 
+pub async fn change_email(uid: Uid, email: CompactString) -> Result<(), ServerError> {
+    if email.len() > 50 {
+        return Err(ServerError::Fuck);
+    }
+    db::change_email(uid, email)
+        .await
+        .map_err(|err| err.map_left(ServerError::into_internal).either_into())
+}
+
+/// Writes every user again, so the columns beside the json match it.
+pub async fn rebuild_user_columns() -> Result<u64, ServerError> {
+    let users = db::all_users().await.map_err(ServerError::into_internal)?;
+    for user in &users {
+        db::set_user(None, user.uid, user)
+            .await
+            .map_err(ServerError::into_internal)?;
+    }
+    Ok(users.len() as u64)
+}
+
 pub async fn remove_token(token: Token) -> Result<(), ServerError> {
     cache::remove_token(token).await;
     db::remove_token(None, token).await.unwrap();
@@ -0,0 +1,92 @@
+//! Statistics of a user computed from the records table, cached for a short while.
+
+use super::*;
+use crate::db::DB;
+use crate::problem::owned_problems;
+use crate::record::search_records;
+use papaya::HashMap;
+use shared::problem::Pid;
+use shared::record::{RecordFlag, RecordQuery};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+const RECENT_RECORDS: u64 = 20;
+/// Seconds a profile is served from the cache, records of the user finishing drop it earlier.
+const PROFILE_EXPIRE: i64 = 60;
+
+#[dynamic]
+static PROFILES: HashMap<Uid, (i64, Arc<UserProfile>)> = HashMap::new();
+
+pub async fn get_user_profile(uid: Uid) -> Result<Arc<UserProfile>, ServerError> {
+    let now = chrono::Utc::now().timestamp();
+    if let Some((time, profile)) = PROFILES.pin().get(&uid)
+        && now - time < PROFILE_EXPIRE
+    {
+        return Ok(profile.clone());
+    }
+    let user = get_user(uid).await?.ok_or(ServerError::NotFound)?;
+    let profile = compute_profile(&user).await?;
+    let profile = Arc::new(profile);
+    PROFILES.pin().insert(uid, (now, profile.clone()));
+    Ok(profile)
+}
+
+pub async fn forget_profile(uid: Uid) {
+    PROFILES.pin().remove(&uid);
+}
+
+async fn compute_profile(user: &User) -> Result<UserProfile, ServerError> {
+    let db = DB.get().unwrap();
+    let uid = user.uid.0 as i64;
+    let groups = sqlx::query!(
+        r#"SELECT pid,flag,COUNT(*) AS "count!: i64" FROM records WHERE uid=$1 GROUP BY pid,flag"#,
+        uid
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ServerError::into_internal)?;
+
+    let mut accepted = BTreeSet::new();
+    let mut attempted = BTreeSet::new();
+    let mut submissions = 0;
+    for group in &groups {
+        submissions += group.count as u64;
+        if group.flag == RecordFlag::Ac.as_str() {
+            accepted.insert(group.pid.as_str());
+        } else {
+            attempted.insert(group.pid.as_str());
+        }
+    }
+    let flags = RecordFlag::ALL
+        .into_iter()
+        .filter_map(|flag| {
+            let count: i64 = groups
+                .iter()
+                .filter(|group| group.flag == flag.as_str())
+                .map(|group| group.count)
+                .sum();
+            (count > 0).then_some((flag, count as u64))
+        })
+        .collect();
+
+    let query = RecordQuery {
+        uid: Some(user.uid),
+        ..Default::default()
+    };
+    let recent = search_records(&query, RECENT_RECORDS).await?;
+    let owned = owned_problems(user.uid).await?;
+
+    Ok(UserProfile {
+        display: user.display(),
+        created_time: user.created_time,
+        accepted: accepted.iter().map(|pid| Pid::new(pid)).collect(),
+        attempted: attempted
+            .difference(&accepted)
+            .map(|pid| Pid::new(pid))
+            .collect(),
+        submissions,
+        flags,
+        recent,
+        owned,
+    })
+}
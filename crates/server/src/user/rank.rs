@@ -0,0 +1,115 @@
+//! Users ranked by distinct problems accepted, fewer submissions first on ties. Built from
+//! the records table once at startup and kept up to date as records are submitted and finish.
+
+use super::*;
+use crate::db::DB;
+use shared::problem::Pid;
+use shared::record::RecordFlag;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use tokio::sync::RwLock;
+
+const PAGE_SIZE: u64 = 50;
+
+#[derive(Debug, Default)]
+struct UserStat {
+    /// Accepted records of each problem.
+    accepted: HashMap<Pid, u64>,
+    submissions: u64,
+}
+
+type RankKey = (Reverse<u32>, u64, Uid);
+
+impl UserStat {
+    fn key(&self, uid: Uid) -> RankKey {
+        (Reverse(self.accepted.len() as u32), self.submissions, uid)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Ranklist {
+    stats: HashMap<Uid, UserStat>,
+    order: BTreeSet<RankKey>,
+}
+
+impl Ranklist {
+    fn update(&mut self, uid: Uid, callback: impl FnOnce(&mut UserStat)) {
+        let stat = self.stats.entry(uid).or_default();
+        self.order.remove(&stat.key(uid));
+        callback(stat);
+        self.order.insert(stat.key(uid));
+    }
+}
+
+#[dynamic]
+static RANKLIST: RwLock<Ranklist> = RwLock::new(Ranklist::default());
+
+pub async fn init_ranklist() -> eyre::Result<()> {
+    let db = DB.get().unwrap();
+    let groups = sqlx::query!(
+        r#"SELECT uid,pid,flag,COUNT(*) AS "count!: i64" FROM records GROUP BY uid,pid,flag"#
+    )
+    .fetch_all(db)
+    .await?;
+    let mut ranklist = RANKLIST.write().await;
+    for group in groups {
+        let uid = Uid(group.uid as u64);
+        let count = group.count as u64;
+        ranklist.update(uid, |stat| {
+            stat.submissions += count;
+            if group.flag == RecordFlag::Ac.as_str() {
+                *stat.accepted.entry(Pid::new(&group.pid)).or_default() += count;
+            }
+        });
+    }
+    Ok(())
+}
+
+pub async fn rank_submitted(uid: Uid) {
+    RANKLIST
+        .write()
+        .await
+        .update(uid, |stat| stat.submissions += 1);
+}
+
+/// Counts an accepted record of a user, or takes one back when it gets rejudged.
+pub async fn rank_accepted(uid: Uid, pid: &Pid, accepted: bool) {
+    RANKLIST.write().await.update(uid, |stat| {
+        if accepted {
+            *stat.accepted.entry(pid.clone()).or_default() += 1;
+        } else if let Some(count) = stat.accepted.get_mut(pid) {
+            *count -= 1;
+            if *count == 0 {
+                stat.accepted.remove(pid);
+            }
+        }
+    });
+}
+
+pub async fn get_ranklist_page_count() -> u64 {
+    (RANKLIST.read().await.order.len() as u64).div_ceil(PAGE_SIZE)
+}
+
+pub async fn get_ranklist_page(index: u64) -> Result<Vec<RankEntry>, ServerError> {
+    let offset = index * PAGE_SIZE;
+    let keys: Vec<_> = RANKLIST
+        .read()
+        .await
+        .order
+        .iter()
+        .skip(offset as usize)
+        .take(PAGE_SIZE as usize)
+        .copied()
+        .collect();
+    let mut ret = Vec::new();
+    for (idx, (Reverse(solved), submissions, uid)) in keys.into_iter().enumerate() {
+        let user = get_user(uid).await?.ok_or(ServerError::BadData)?;
+        ret.push(RankEntry {
+            rank: offset + idx as u64 + 1,
+            user: user.display(),
+            solved,
+            submissions,
+        });
+    }
+    Ok(ret)
+}
@@ -0,0 +1,65 @@
+//! How the judge compares an output with the answer.
+
+/// Byte offsets in `output` and `answer` of the first token that differs,
+/// comparing line by line and token by token so whitespace does not matter.
+/// The judge accepts an output when there is none.
+pub fn first_difference(output: &str, answer: &str) -> Option<(usize, usize)> {
+    let offset = |text: &str, part: &str| part.as_ptr() as usize - text.as_ptr() as usize;
+    let end = |text: &str, line: &str| offset(text, line) + line.len();
+    let mut output_lines = output.lines();
+    let mut answer_lines = answer.lines();
+    loop {
+        let (output_line, answer_line) = match (output_lines.next(), answer_lines.next()) {
+            (None, None) => return None,
+            (Some(a), None) => return Some((offset(output, a), answer.len())),
+            (None, Some(b)) => return Some((output.len(), offset(answer, b))),
+            (Some(a), Some(b)) => (a, b),
+        };
+        let mut output_tokens = output_line.split_whitespace();
+        let mut answer_tokens = answer_line.split_whitespace();
+        loop {
+            match (output_tokens.next(), answer_tokens.next()) {
+                (None, None) => break,
+                (Some(a), Some(b)) if a == b => {}
+                (a, b) => {
+                    let a = a.map_or(end(output, output_line), |a| offset(output, a));
+                    let b = b.map_or(end(answer, answer_line), |b| offset(answer, b));
+                    return Some((a, b));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::first_difference as diff;
+
+    #[test]
+    fn equal() {
+        assert_eq!(diff("1 2\n3\n", "1 2\n3\n"), None);
+        assert_eq!(diff("", ""), None);
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(diff("1 2", "1 2 3"), Some((3, 4)));
+        assert_eq!(diff("1 2 3\n", "1 2"), Some((4, 3)));
+        assert_eq!(diff("1\n", "1\n2\n"), Some((2, 2)));
+        assert_eq!(diff("1\n2\n", "1\n"), Some((2, 2)));
+    }
+
+    #[test]
+    fn whitespace_only() {
+        assert_eq!(diff("1  2 \r\n3", "1 2\n3\n"), None);
+        assert_eq!(diff("1\t2\n", "1 2"), None);
+        assert_eq!(diff("1 2\n", "1\n2\n"), Some((2, 1)));
+    }
+
+    #[test]
+    fn multibyte() {
+        assert_eq!(diff("你好 世界", "你好 世界"), None);
+        assert_eq!(diff("你好  世界\n", "你好 世间\n"), Some((8, 7)));
+        assert_eq!(diff("é 1", "é 2"), Some((3, 3)));
+    }
+}
@@ -9,10 +9,30 @@ pub enum FrontMessage {
     GetProblemFileMeta(Pid, CompactString),
     GetProblemsPageCount,
     GetProblemsPage(u64),
+    CreateProblem(Pid),
     RequireProblemFileDownloadToken(Pid, CompactString),
     GetRecord(Rid),
     Submit(Submission),
     GetLoginedUser,
     RegisterUser(UserRegistration),
+    ChangeNickname(CompactString),
+    ChangeEmail(CompactString),
+    ChangePassword(PasswordChange),
+    GrantRole(Uid, Role),
+    RevokeRole(Uid, Role),
+    ListUsers(UserQuery),
+    ForceLogout(Uid),
+    ResetPassword(Uid, CompactString),
+    CreateBackup,
+    GetRecords(RecordQuery),
     CheckJudgeMachines,
+    GetJudgeQueue,
+    Rejudge(Rid),
+    RejudgeProblem(Pid),
+    StartCustomRun(CustomRun),
+    GetCustomRun(RunId),
+    GetCaseOutput(Rid, usize),
+    GetUserProfile(Uid),
+    GetRanklistPage(u64),
+    GetRanklistPageCount,
 }
@@ -7,17 +7,76 @@ pub enum JudgeMessage {
     GetProblemData(Pid),
     GetRecord(Rid),
     GetProblemFile(Pid, CompactString),
-    SendCompileResult(Rid, CompileResult),
-    SendSingleJudgeResult(Rid, usize, SingleJudgeResult),
-    SendAllJudgeResults(Rid, AllJudgeResult),
+    /// Results carry the uuid of the sending machine, so that only the holder
+    /// of a task can report on it.
+    SendCompileResult(uuid::Uuid, Rid, CompileResult),
+    /// The beginning of the output comes along for sample testcases.
+    SendSingleJudgeResult(uuid::Uuid, Rid, usize, SingleJudgeResult, Option<String>),
+    SendAllJudgeResults(uuid::Uuid, Rid, AllJudgeResult),
+    GetCustomRun(RunId),
+    SendCustomRunResult(uuid::Uuid, RunId, CustomRunStatus),
+    /// The output kept for a testcase, `None` if the judge does not have it anymore.
+    SendCaseOutput(uuid::Uuid, Rid, usize, Option<String>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum JudgeCommand {
     Judge(Rid),
+    Run(RunId),
+    /// Asks for the output the judge kept for a failed testcase.
+    UploadCaseOutput(Rid, usize),
     Null,
 }
 
+/// Something waiting for or running on a judge machine.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum JudgeTask {
+    Record(Rid),
+    Run(RunId),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RunId(pub u64);
+
+impl std::fmt::Display for RunId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Code run against custom input on the limits of a problem, never stored as a record.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+pub struct CustomRun {
+    pub pid: Pid,
+    pub code: String,
+    pub stdin: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomRunOutput {
+    /// `Ac` merely means the program exited normally.
+    pub result: SingleJudgeResult,
+    /// The beginning of the output, `CUSTOM_RUN_OUTPUT_LIMIT` bytes at most.
+    pub stdout: String,
+    pub stderr: String,
+}
+
+pub const CUSTOM_RUN_OUTPUT_LIMIT: usize = 64 << 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum CustomRunStatus {
+    Waiting,
+    Running,
+    CompileError(CompileError),
+    Finished(CustomRunOutput),
+}
+
+impl CustomRunStatus {
+    pub fn done(&self) -> bool {
+        matches!(self, Self::CompileError(_) | Self::Finished(_))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct JudgeMachineSignal {
     pub cpu_usage: u32,
@@ -25,10 +84,58 @@ pub struct JudgeMachineSignal {
     pub system_name: Option<String>,
     pub hostname: Option<String>,
     pub tasks: Vec<Rid>,
+    #[serde(default)]
+    pub runs: Vec<RunId>,
+    pub capacity: u32,
+    /// Score of a fixed single-threaded workload, higher is faster.
+    #[serde(default)]
+    pub cpu_benchmark: u32,
     pub uuid: uuid::Uuid,
     pub timestamp: u64,
 }
 
+/// Where a record is placed in the judge queue, the smaller the earlier.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JudgePriority {
+    /// Submitted during a contest, judged before practice.
+    Contest,
+    Submission,
+    Rejudge,
+    Run,
+}
+
+impl JudgePriority {
+    pub const ALL: [JudgePriority; 4] = [Self::Contest, Self::Submission, Self::Rejudge, Self::Run];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|priority| priority.as_str() == s)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Contest => "Contest",
+            Self::Submission => "Submission",
+            Self::Rejudge => "Rejudge",
+            Self::Run => "Run",
+        }
+    }
+}
+
+impl std::fmt::Display for JudgePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueuedTask {
+    pub task: JudgeTask,
+    pub uid: Uid,
+    pub priority: JudgePriority,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum Verdict {
     Ac,
@@ -36,6 +143,7 @@ pub enum Verdict {
     Re,
     Tle,
     Mle,
+    Ole,
     Uke,
 }
 
@@ -47,6 +155,7 @@ impl Verdict {
             Verdict::Tle => 2,
             Verdict::Wa => 3,
             Verdict::Re => 4,
+            Verdict::Ole => 5,
             Verdict::Uke => 6,
         }
     }
@@ -57,6 +166,7 @@ impl Verdict {
             Self::Re => RecordFlag::Re,
             Self::Tle => RecordFlag::Tle,
             Self::Mle => RecordFlag::Mle,
+            Self::Ole => RecordFlag::Ole,
             Self::Uke => RecordFlag::Uke,
         }
     }
@@ -80,6 +190,9 @@ impl std::fmt::Display for Verdict {
             Self::Mle => {
                 write!(f, "Memory Limit Exceed")
             }
+            Self::Ole => {
+                write!(f, "Output Limit Exceed")
+            }
             Self::Uke => {
                 write!(f, "Unknown Error")
             }
@@ -97,7 +210,59 @@ pub enum CompileResult {
 pub struct SingleJudgeResult {
     pub verdict: Verdict,
     pub memory_used: u32,
+    /// Cpu time in ms.
     pub time_used: u32,
+    /// Wall time in ms, missing in records judged before it was measured.
+    #[serde(default)]
+    pub wall_time: u32,
+    /// How the program ended, kept for runtime errors.
+    #[serde(default)]
+    pub termination: Option<Termination>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Termination {
+    ExitCode(i32),
+    Signal(i32),
+}
+
+/// Names of the linux signals a program usually dies from.
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        10 => "SIGUSR1",
+        11 => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        16 => "SIGSTKFLT",
+        24 => "SIGXCPU",
+        25 => "SIGXFSZ",
+        31 => "SIGSYS",
+        _ => return None,
+    };
+    Some(name)
+}
+
+impl std::fmt::Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExitCode(code) => write!(f, "exit code {code}"),
+            Self::Signal(signal) => match signal_name(*signal) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "signal {signal}"),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -111,8 +276,31 @@ pub struct AllJudgeResult {
 
 #[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error, PartialEq)]
 pub struct CompileError {
+    /// The compile log, truncated to its beginning.
     pub message: String,
     pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub kind: CompileErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum CompileErrorKind {
+    /// The compiler rejected the code.
+    #[default]
+    Failed,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+}
+
+impl std::fmt::Display for CompileErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CompileErrorKind::Failed => "Compile Error",
+            CompileErrorKind::TimeLimitExceeded => "Compile Time Limit Exceeded",
+            CompileErrorKind::MemoryLimitExceeded => "Compile Memory Limit Exceeded",
+        };
+        write!(f, "{s}")
+    }
 }
 
 impl std::fmt::Display for CompileError {
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+pub mod comp;
 pub mod constant;
 pub mod download;
 pub mod error;
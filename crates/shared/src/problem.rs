@@ -21,11 +21,29 @@ pub struct ProblemFront {
     pub time_limit: u32,
     pub memory_limit: u32,
     pub public_files: Vec<CompactString>,
+    #[serde(default)]
+    pub run_limits: RunLimits,
+    #[serde(default)]
+    pub file_io: Option<FileIo>,
+    #[serde(default)]
+    pub samples: Vec<Sample>,
 }
 
+/// A sample testcase shown in the statement.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Sample {
+    /// Index of the testcase.
+    pub case: usize,
+    pub input: String,
+    pub output: String,
+}
+
+/// Bytes of sample files shown, in the statement as well as in records.
+pub const SAMPLE_LIMIT: usize = 64 << 10;
+
 impl ProblemFront {
     pub fn can_be_edited_by(&self, user: &LoginedUser) -> bool {
-        self.owner == Some(user.uid) || user.privilege.edit_problems
+        self.owner == Some(user.uid) || user.privilege.has(Permission::EditProblems)
     }
 }
 
@@ -33,6 +51,38 @@ impl ProblemFront {
 pub struct Testcase {
     pub input_file: CompactString,
     pub output_file: CompactString,
+    /// Shown in the statement, and in full to the submitter of a record.
+    #[serde(default)]
+    pub is_sample: bool,
+}
+
+/// Files a program reads its input from and writes its answer to, instead of stdin and stdout.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileIo {
+    pub input: CompactString,
+    pub output: CompactString,
+}
+
+/// Limits besides time and memory, the defaults fit a single-threaded program.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct RunLimits {
+    /// Stack size in mb, as large as the memory limit when missing.
+    /// Threads take it as their default stack size as well.
+    pub stack: Option<u32>,
+    /// Processes or threads running at once, the program itself included.
+    pub processes: u32,
+    pub open_files: u32,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        RunLimits {
+            stack: None,
+            processes: 1,
+            open_files: 64,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -52,6 +102,10 @@ pub struct ProblemData {
     pub files: Vec<ProblemFile>,
     pub time_limit: u32,
     pub memory_limit: u32,
+    #[serde(default)]
+    pub run_limits: RunLimits,
+    #[serde(default)]
+    pub file_io: Option<FileIo>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -12,7 +12,7 @@ pub enum RecordStatus {
     Completed(AllJudgeResult),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RecordFlag {
     Waiting,
     Compiling,
@@ -22,11 +22,23 @@ pub enum RecordFlag {
     Re,
     Tle,
     Mle,
+    Ole,
     Ce,
     Uke,
 }
 
 impl RecordFlag {
+    pub const ALL: [RecordFlag; 11] = {
+        use RecordFlag::*;
+        [
+            Waiting, Compiling, Running, Ac, Wa, Re, Tle, Mle, Ole, Ce, Uke,
+        ]
+    };
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|flag| flag.as_str() == s)
+    }
+
     pub fn as_str(&self) -> &'static str {
         use RecordFlag::*;
         match self {
@@ -37,11 +49,27 @@ impl RecordFlag {
             Wa => "WA",
             Tle => "TLE",
             Mle => "MLE",
+            Ole => "OLE",
             Ce => "Compile Error",
             Re => "RE",
             Uke => "Unknown Error",
         }
     }
+
+    /// The verdict of a judged record, `None` while it waits or when it failed to compile.
+    pub fn verdict(&self) -> Option<Verdict> {
+        use RecordFlag::*;
+        match self {
+            Ac => Some(Verdict::Ac),
+            Wa => Some(Verdict::Wa),
+            Re => Some(Verdict::Re),
+            Tle => Some(Verdict::Tle),
+            Mle => Some(Verdict::Mle),
+            Ole => Some(Verdict::Ole),
+            Uke => Some(Verdict::Uke),
+            Waiting | Compiling | Running | Ce => None,
+        }
+    }
 }
 
 impl RecordStatus {
@@ -90,6 +118,12 @@ pub struct Record {
     pub code: String,
     pub status: RecordStatus,
     pub time: i64,
+    /// Testcases that were samples when the record was judged.
+    #[serde(default)]
+    pub samples: Vec<usize>,
+    /// Beginnings of the outputs of sample testcases by their index.
+    #[serde(default)]
+    pub sample_outputs: std::collections::BTreeMap<usize, String>,
 }
 
 impl Record {
@@ -100,6 +134,7 @@ impl Record {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum RecordMessage {
+    Waiting,
     Compiling,
     Compiled(usize),
     CompileError(CompileError),
@@ -121,31 +156,65 @@ impl FromStr for Rid {
     }
 }
 
-#[cfg(feature = "server")]
-mod native {
-    use super::Record;
-    use crate::from_json_in_row;
-    use sqlx::sqlite::SqliteRow;
-    use sqlx::{FromRow, SqlitePool};
-    impl FromRow<'_, SqliteRow> for Record {
-        fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
-            from_json_in_row(row)
-        }
-    }
-    impl Record {
-        pub async fn insert_db(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
-            sqlx::query(
-                "INSERT INTO records (rid,uid,pid,flag,time,json) VALUES ($1,$2,$3,$4,$5,$6)",
-            )
-            .bind(self.rid.0 as i64)
-            .bind(self.uid.0 as i64)
-            .bind(self.pid.0.as_str())
-            .bind(self.flag().as_str())
-            .bind(self.time)
-            .bind(serde_json::to_string(self).unwrap())
-            .execute(pool)
-            .await?;
-            Ok(())
+/// A record in lists, without its code and results.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecordSummary {
+    pub rid: Rid,
+    pub pid: Pid,
+    pub uid: Uid,
+    pub flag: RecordFlag,
+    pub time: i64,
+    /// Only known once the record is judged.
+    pub max_time: Option<u32>,
+    pub memory_used: Option<u32>,
+}
+
+pub const RECORDS_PAGE_SIZE: u64 = 50;
+
+/// Which records to list, latest first. Fields left `None` match every record.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Default)]
+pub struct RecordQuery {
+    pub pid: Option<Pid>,
+    pub uid: Option<Uid>,
+    pub flag: Option<RecordFlag>,
+    /// Submitted at this unix time or later.
+    pub since: Option<i64>,
+    pub index: u64,
+}
+
+/// Bytes kept from the output of a failed testcase and from its answer.
+pub const CASE_OUTPUT_LIMIT: usize = 4 << 10;
+
+/// The beginning of the output of a testcase next to the expected answer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaseDiff {
+    /// The input as well, only for sample testcases.
+    #[serde(default)]
+    pub input: Option<String>,
+    pub output: String,
+    pub answer: String,
+    /// Byte offsets in the output and the answer of the first token that differs,
+    /// `None` if the kept parts are the same to the judge.
+    pub first_difference: Option<(usize, usize)>,
+}
+
+impl CaseDiff {
+    pub fn new(output: String, answer: String) -> Self {
+        let first_difference = crate::comp::first_difference(&output, &answer);
+        CaseDiff {
+            input: None,
+            output,
+            answer,
+            first_difference,
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum CaseOutputStatus {
+    /// Waiting for the judge machine to upload it.
+    Requested,
+    /// The judge machine is gone or no longer has it.
+    Unavailable,
+    Ready(CaseDiff),
+}
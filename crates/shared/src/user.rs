@@ -1,9 +1,22 @@
 use super::*;
 use compact_str::CompactString;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Copy, PartialOrd, Ord)]
 pub struct Uid(pub u64);
 
+impl std::fmt::Display for Uid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for Uid {
+    type Err = <u64 as std::str::FromStr>::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Uid(s.parse()?))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
 pub struct UserRegistration {
     pub email: CompactString,
@@ -12,12 +25,68 @@ pub struct UserRegistration {
     pub username: CompactString,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
+pub struct PasswordChange {
+    pub old: CompactString,
+    pub new: CompactString,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct UserDisplay {
     pub nickname: CompactString,
     pub uid: Uid,
 }
 
+/// What everyone can see about a user, the statistics come from their records.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserProfile {
+    pub display: UserDisplay,
+    pub created_time: i64,
+    pub accepted: Vec<Pid>,
+    /// Problems submitted to but never accepted.
+    pub attempted: Vec<Pid>,
+    pub submissions: u64,
+    /// Number of records with each flag, flags without records are left out.
+    pub flags: Vec<(RecordFlag, u64)>,
+    /// Latest first.
+    pub recent: Vec<RecordSummary>,
+    #[serde(default)]
+    pub owned: Vec<ProblemProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RankEntry {
+    pub rank: u64,
+    pub user: UserDisplay,
+    /// Distinct problems accepted.
+    pub solved: u32,
+    pub submissions: u64,
+}
+
+/// A user as listed to those managing users.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserSummary {
+    pub uid: Uid,
+    pub username: CompactString,
+    pub nickname: CompactString,
+    pub email: CompactString,
+    pub privilege: Privilege,
+    pub created_time: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
+pub struct UserQuery {
+    /// Part of the username or email, empty for everyone.
+    pub search: CompactString,
+    pub index: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserList {
+    pub users: Vec<UserSummary>,
+    pub page_count: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
 pub struct LoginedUser {
     pub uid: Uid,
@@ -26,25 +95,146 @@ pub struct LoginedUser {
     pub privilege: Privilege,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq)]
+/// Something only some users may do, granted through roles.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Hash, Eq)]
+pub enum Permission {
+    EnterSite,
+    CreateProblems,
+    /// Edit any problem, owners can always edit their own.
+    EditProblems,
+    ManageUsers,
+    Rejudge,
+    /// See the code of every record, not only their own.
+    ViewAllCode,
+    ManageJudges,
+    /// Set up and run contests.
+    ManageContests,
+    /// Take backups of the database and problem files.
+    Backup,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Hash, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Admin,
+    ProblemSetter,
+    ContestManager,
+    JudgeOperator,
+    /// Takes away every permission, entering the site included.
+    Banned,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [
+        Role::Admin,
+        Role::ProblemSetter,
+        Role::ContestManager,
+        Role::JudgeOperator,
+        Role::Banned,
+    ];
+
+    pub fn permissions(self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Admin => &[
+                EnterSite,
+                CreateProblems,
+                EditProblems,
+                ManageUsers,
+                Rejudge,
+                ViewAllCode,
+                ManageJudges,
+                ManageContests,
+                Backup,
+            ],
+            Role::ProblemSetter => &[CreateProblems],
+            Role::ContestManager => &[ManageContests, ViewAllCode],
+            Role::JudgeOperator => &[ManageJudges, Rejudge],
+            Role::Banned => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Role::Admin => "admin",
+            Role::ProblemSetter => "problem setter",
+            Role::ContestManager => "contest manager",
+            Role::JudgeOperator => "judge operator",
+            Role::Banned => "banned",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Eq, Default)]
+#[serde(from = "PrivilegeRepr")]
 pub struct Privilege {
-    pub edit_problems: bool,
-    pub enter_site: bool,
+    pub roles: Vec<Role>,
+}
+
+/// Also reads users stored before roles, which only had two flags.
+#[derive(Deserialize)]
+struct PrivilegeRepr {
+    #[serde(default)]
+    roles: Vec<Role>,
+    edit_problems: Option<bool>,
+    enter_site: Option<bool>,
+}
+
+impl From<PrivilegeRepr> for Privilege {
+    fn from(repr: PrivilegeRepr) -> Self {
+        let mut privilege = Privilege { roles: repr.roles };
+        if repr.edit_problems == Some(true) {
+            privilege.grant(Role::Admin);
+        }
+        if repr.enter_site == Some(false) {
+            privilege.grant(Role::Banned);
+        }
+        privilege
+    }
 }
 
 impl Privilege {
-    pub const DEFAULT: Self = Self {
-        edit_problems: false,
-        enter_site: true,
-    };
-    pub const ALL: Self = Self {
-        edit_problems: true,
-        enter_site: true,
-    };
-}
-
-impl Default for Privilege {
-    fn default() -> Self {
-        Self::DEFAULT
+    pub const DEFAULT: Self = Self { roles: Vec::new() };
+
+    pub fn has(&self, permission: Permission) -> bool {
+        if self.roles.contains(&Role::Banned) {
+            return false;
+        }
+        permission == Permission::EnterSite
+            || self
+                .roles
+                .iter()
+                .any(|role| role.permissions().contains(&permission))
+    }
+
+    pub fn grant(&mut self, role: Role) {
+        if !self.roles.contains(&role) {
+            self.roles.push(role);
+            self.roles.sort();
+        }
+    }
+
+    pub fn revoke(&mut self, role: Role) {
+        self.roles.retain(|x| *x != role);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_flags_become_roles() {
+        let json = r#"{"edit_problems":true,"enter_site":true}"#;
+        let privilege: Privilege = serde_json::from_str(json).unwrap();
+        assert_eq!(privilege.roles, vec![Role::Admin]);
+    }
+
+    #[test]
+    fn unknown_roles_fail() {
+        let json = r#"{"roles":["Admin","Moderator"]}"#;
+        assert!(serde_json::from_str::<Privilege>(json).is_err());
     }
 }
@@ -1,5 +1,6 @@
 use std::ffi::OsStr;
 use std::future::Future;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -15,11 +16,37 @@ compile_error!("The target platform is not supported, only on linux");
 
 #[derive(Debug, Clone)]
 pub struct RunResult {
-    pub time_used: Duration,
+    /// User plus system time.
+    pub cpu_time: Duration,
+    pub wall_time: Duration,
     pub memory_used: u64,
     pub exit_code: Option<i32>,
+    /// The signal that terminated the program, if any.
+    pub signal: Option<i32>,
     pub status: Status,
     pub stdout: Vec<u8>,
+    /// Content of `FileIo::output`, `None` if the program never created it.
+    pub output_file: Option<Vec<u8>>,
+    /// At most `STDERR_LIMIT` bytes from the start of stderr.
+    pub stderr: Vec<u8>,
+}
+
+pub const STDERR_LIMIT: u64 = 64 << 10;
+
+/// Reads at most `limit` bytes, also telling whether the stream had more.
+pub(crate) fn read_limited(reader: impl Read, limit: u64) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut data = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut data)?;
+    let exceeded = data.len() as u64 > limit;
+    data.truncate(limit as usize);
+    Ok((data, exceeded))
+}
+
+/// Keeps a prefix of stderr and drains the rest so the program never blocks on it.
+pub(crate) fn read_stderr(mut reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let (data, _) = read_limited(&mut reader, STDERR_LIMIT)?;
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    Ok(data)
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -27,12 +54,21 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[cfg(target_os = "linux")]
 pub type PlatformTestBox = linux::LinuxTestBox;
 
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub use linux::NativeTestBox;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Status {
     Okay,
     RuntimeError,
     TimeLimitExceed,
     MemoryLimitExceed,
+    OutputLimitExceeded,
+    /// Spent too long off the cpu, like waiting for input that never comes.
+    IdleLimitExceeded,
 }
 
 pub trait TestBox
@@ -46,11 +82,92 @@ where
         args: impl IntoIterator<Item = &'a OsStr>,
         stdin: impl AsRef<[u8]>,
     ) -> impl Future<Output = Result<RunResult>>;
+    /// Runs a trusted host program like a compiler under the same limits, with `dir` as its
+    /// writable working directory. Unlike `run_single` it may spawn processes of its own.
+    fn run_command<'a>(
+        &self,
+        dir: impl AsRef<Path>,
+        program: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item = &'a OsStr>,
+    ) -> impl Future<Output = Result<RunResult>>;
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub root: PathBuf,
     pub memory_limit: u64,
-    pub time_limit: Duration,
+    /// User plus system time.
+    pub cpu_time_limit: Duration,
+    pub wall_time_limit: Duration,
+    /// Wall time not spent on the cpu.
+    pub idle_limit: Duration,
+    /// Bytes of stdout kept, the program is stopped once it writes more.
+    pub output_limit: u64,
+    /// Bytes of stack, often as large as the memory limit for deep recursion.
+    pub stack_limit: u64,
+    /// Processes or threads running at once, the program itself included.
+    /// Programs in the native sandbox may only start threads.
+    pub process_limit: u32,
+    pub open_files_limit: u64,
+    pub file_io: Option<FileIo>,
+    /// A cgroup v2 delegated to the judge, each run gets its own child cgroup in it.
+    /// Without one the limits fall back to rlimits and polling.
+    pub cgroup: Option<PathBuf>,
+}
+
+impl Config {
+    /// Seconds of the cpu rlimit, a backstop past the limit so the watcher reports it first.
+    pub(crate) fn rlimit_cpu(&self) -> u64 {
+        self.cpu_time_limit.as_secs_f64().ceil() as u64 + 1
+    }
+
+    /// Bytes of the address space rlimit used without a cgroup. Threads reserve a stack
+    /// of `stack_limit` each, which has to fit besides the memory itself.
+    pub(crate) fn rlimit_as(&self) -> u64 {
+        let threads = self.process_limit.saturating_sub(1) as u64;
+        (self.memory_limit * 2).saturating_add(threads.saturating_mul(self.stack_limit))
+    }
+}
+
+/// Plain file names in the working directory of the program, used instead of stdin and stdout.
+#[derive(Debug, Clone)]
+pub struct FileIo {
+    pub input: String,
+    pub output: String,
+}
+
+impl FileIo {
+    fn check(&self) -> Result<()> {
+        for name in [&self.input, &self.output] {
+            if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+                return Err(Error::OtherError(format!("bad file name {name:?}")));
+            }
+        }
+        Ok(())
+    }
+
+    /// Places the input into `dir` before a run.
+    pub(crate) async fn place_input(&self, dir: &Path, data: &[u8]) -> Result<()> {
+        self.check()?;
+        tokio::fs::write(dir.join(&self.input), data).await?;
+        Ok(())
+    }
+
+    /// Reads the output from `dir` after a run, also telling whether it broke the limit.
+    pub(crate) async fn collect_output(
+        &self,
+        dir: &Path,
+        limit: u64,
+    ) -> Result<Option<(Vec<u8>, bool)>> {
+        let path = dir.join(&self.output);
+        let file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let ret = tokio::task::spawn_blocking(move || read_limited(file, limit))
+            .await
+            .map_err(map_err)??;
+        Ok(Some(ret))
+    }
 }
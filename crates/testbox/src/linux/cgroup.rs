@@ -0,0 +1,120 @@
+use super::*;
+use std::fs::File;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// `cpu.max` period in microseconds, the quota equals it so a run gets one core.
+const CPU_PERIOD: u32 = 100_000;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A cgroup v2 created for a single run, removed on drop.
+#[derive(Debug)]
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    /// Creates a child cgroup of `parent`, which should be delegated to the current user.
+    pub async fn create(parent: &Path, memory_limit: u64, pids_max: u32) -> Result<Self> {
+        // the controllers may have been enabled already, or be managed by someone else
+        if let Err(err) =
+            fs::write(parent.join("cgroup.subtree_control"), "+memory +pids +cpu").await
+        {
+            tracing::warn!("fail to enable cgroup controllers {err:?}");
+        }
+
+        let name = format!(
+            "testbox-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = parent.join(name);
+        fs::create_dir(&path).await?;
+        let cgroup = Cgroup { path };
+
+        cgroup.write("memory.max", memory_limit).await?;
+        if let Err(err) = cgroup.write("memory.swap.max", 0).await {
+            tracing::warn!("fail to disable swap {err:?}");
+        }
+        cgroup.write("pids.max", pids_max).await?;
+        cgroup
+            .write("cpu.max", format!("{CPU_PERIOD} {CPU_PERIOD}"))
+            .await?;
+
+        Ok(cgroup)
+    }
+
+    async fn write(&self, file: &str, value: impl ToString) -> Result<()> {
+        fs::write(self.path.join(file), value.to_string()).await?;
+        Ok(())
+    }
+
+    async fn read_key(&self, file: &str, key: &str) -> Result<u64> {
+        let content = fs::read_to_string(self.path.join(file)).await?;
+        content
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, v)| v.trim().parse().ok())
+            .ok_or_else(|| Error::OtherError(format!("no {key} in {file}")))
+    }
+
+    /// `cgroup.procs` opened for writing, a child writes `0` into it to join the cgroup.
+    pub fn procs(&self) -> Result<File> {
+        Ok(File::options()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))?)
+    }
+
+    /// Moves a process into the cgroup.
+    pub async fn attach(&self, pid: u32) -> Result<()> {
+        self.write("cgroup.procs", pid).await
+    }
+
+    /// Kills every process in the cgroup.
+    pub async fn kill(&self) -> Result<()> {
+        self.write("cgroup.kill", 1).await
+    }
+
+    /// Peak memory usage in bytes, `None` on kernels without `memory.peak`.
+    pub async fn memory_peak(&self) -> Result<Option<u64>> {
+        match fs::read_to_string(self.path.join("memory.peak")).await {
+            Ok(content) => Ok(content.trim().parse().ok()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Cpu time consumed by the whole cgroup.
+    pub async fn cpu_time(&self) -> Result<Duration> {
+        let usec = self.read_key("cpu.stat", "usage_usec").await?;
+        Ok(Duration::from_micros(usec))
+    }
+
+    pub async fn oom_killed(&self) -> Result<bool> {
+        Ok(self.read_key("memory.events", "oom_kill").await? > 0)
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        // a process left behind would keep the cgroup busy
+        if let Err(err) = std::fs::write(self.path.join("cgroup.kill"), "1") {
+            tracing::warn!("fail to kill cgroup {:?} {err:?}", self.path);
+        }
+        // the killed processes take a moment to leave
+        let mut ret = std::fs::remove_dir(&self.path);
+        for _ in 0..100 {
+            match &ret {
+                Err(err) if err.raw_os_error() == Some(libc::EBUSY) => {
+                    std::thread::sleep(Duration::from_millis(1));
+                    ret = std::fs::remove_dir(&self.path);
+                }
+                _ => break,
+            }
+        }
+        if let Err(err) = ret {
+            tracing::warn!("fail to remove cgroup {:?} {err:?}", self.path);
+        }
+    }
+}
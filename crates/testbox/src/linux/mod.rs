@@ -1,9 +1,30 @@
+mod cgroup;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod native;
+mod watch;
+
 use super::*;
+use cgroup::Cgroup;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub use native::NativeTestBox;
 use std::ffi::OsString;
 use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
 use std::process::{Command, Stdio};
 use tokio::fs;
 use wait4::Wait4;
+use watch::{Clock, watch};
+
+/// Processes firejail itself keeps around the program.
+const FIREJAIL_PROCESSES: u32 = 3;
+
+/// Where firejail passes `cgroup.procs` of the run down to the program.
+const CGROUP_PROCS_FD: i32 = 3;
+
+/// Moves the shell into the cgroup before it becomes the program,
+/// so the usage of firejail is not counted.
+const JOIN_CGROUP: &str = "echo 0 >&3 && exec \"$@\"";
 
 #[derive(Debug, Clone)]
 pub struct LinuxTestBox {
@@ -28,102 +49,218 @@ impl TestBox for LinuxTestBox {
         }
         fs::create_dir_all(&self.config.root).await?;
         fs::copy(path, &self.config.root.join("prog")).await?;
+        let stdin = stdin.as_ref();
+        let stdin = match &self.config.file_io {
+            Some(file_io) => {
+                file_io.place_input(&self.config.root, stdin).await?;
+                &[][..]
+            }
+            None => stdin,
+        };
+
+        let mut program = vec![OsString::from("./prog")];
+        program.extend(args.into_iter().map(OsString::from));
+        self.execute(
+            &self.config.root,
+            Vec::new(),
+            program,
+            stdin,
+            self.config.file_io.as_ref(),
+        )
+        .await
+    }
+
+    async fn run_command<'a>(
+        &self,
+        dir: impl AsRef<Path>,
+        program: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item = &'a OsStr>,
+    ) -> Result<RunResult> {
+        let options = vec![OsString::from(format!(
+            "--rlimit-fsize={}",
+            self.config.output_limit + 1
+        ))];
+        let mut program = vec![program.as_ref().to_owned()];
+        program.extend(args.into_iter().map(OsString::from));
+        self.execute(dir.as_ref(), options, program, &[], None)
+            .await
+    }
+}
 
+impl LinuxTestBox {
+    /// Runs `program` in firejail with `dir` as the private home, the limits of the config
+    /// and the remaining firejail `options`.
+    async fn execute(
+        &self,
+        dir: &Path,
+        options: Vec<OsString>,
+        program: Vec<OsString>,
+        stdin: &[u8],
+        file_io: Option<&FileIo>,
+    ) -> Result<RunResult> {
         let mut private = OsString::from("--private=");
-        private.push(&self.config.root);
+        private.push(dir);
+
+        let cgroup = match &self.config.cgroup {
+            Some(parent) => {
+                let pids = self.config.process_limit;
+                Some(Cgroup::create(parent, self.config.memory_limit, pids).await?)
+            }
+            None => None,
+        };
 
         let start = std::time::Instant::now();
 
         let mut command = Command::new("firejail");
 
-        command
-            .arg(&private)
-            .arg(format!("--rlimit-as={}", self.config.memory_limit * 2))
-            .arg(format!(
-                "--rlimit-cpu={}",
-                self.config.time_limit.as_secs_f64().ceil() as u32
-            ))
-            .arg("--rlimit-nproc=4")
-            .arg("./prog");
-
-        for item in args {
-            command.arg(item);
+        command.arg(&private);
+        match &cgroup {
+            Some(cgroup) => {
+                // firejail stays outside, the program joins through the inherited file
+                let procs = cgroup.procs()?;
+                // SAFETY: dup2 is async-signal-safe, and the duplicate is not close-on-exec
+                unsafe {
+                    command.pre_exec(move || {
+                        if libc::dup2(procs.as_raw_fd(), CGROUP_PROCS_FD) < 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                        Ok(())
+                    });
+                }
+                command.arg(format!("--keep-fd={CGROUP_PROCS_FD}"));
+            }
+            None => {
+                command.arg(format!("--rlimit-as={}", self.config.rlimit_as()));
+            }
+        }
+        if file_io.is_some() {
+            command.arg(format!("--rlimit-fsize={}", self.config.output_limit + 1));
+        }
+        command.arg(format!("--rlimit-cpu={}", self.config.rlimit_cpu()));
+        command.arg(format!("--rlimit-nofile={}", self.config.open_files_limit));
+        // the rlimit counts every process of the user, so it is only a rough bound
+        let nproc = self.config.process_limit + FIREJAIL_PROCESSES;
+        command.arg(format!("--rlimit-nproc={nproc}"));
+        // firejail has no option for the stack, it inherits the limit instead
+        let stack = libc::rlimit {
+            rlim_cur: self.config.stack_limit,
+            rlim_max: self.config.stack_limit,
+        };
+        // SAFETY: setrlimit is async-signal-safe
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setrlimit(libc::RLIMIT_STACK, &stack) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        command.args(options);
+        if cgroup.is_some() {
+            command.args(["/bin/sh", "-c", JOIN_CGROUP, "sh"]);
         }
+        command.args(program);
 
         let mut child = command
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null())
+            .stderr(Stdio::piped())
             .spawn()?;
 
         let pid = child.id();
 
-        let stdin_data = stdin.as_ref().to_owned();
+        let stdin_data = stdin.to_owned();
         let mut stdin = child.stdin.take().unwrap();
-        let mut stdout = child.stdout.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
 
-        let stdin = tokio::task::spawn_blocking(move || stdin.write_all(&stdin_data));
-        let stdout = tokio::task::spawn_blocking(move || {
-            let mut data = Vec::new();
-            stdout.read_to_end(&mut data)?;
-            Ok::<_, std::io::Error>(data)
+        let stdin = tokio::task::spawn_blocking(move || match stdin.write_all(&stdin_data) {
+            // the program may exit without reading its input
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            ret => ret,
         });
+        let output_limit = self.config.output_limit;
+        // dropping stdout once the limit is hit stops the program with SIGPIPE
+        let stdout = tokio::task::spawn_blocking(move || read_limited(stdout, output_limit));
+        let stderr = tokio::task::spawn_blocking(move || read_stderr(stderr));
 
         let proc = tokio::task::spawn_blocking(move || child.wait4());
 
-        let mut status = Status::Okay;
-
         let pid = nix::unistd::Pid::from_raw(pid as i32);
-        let kill = async || {
-            let sigkill = nix::sys::signal::SIGKILL;
-            tokio::task::spawn_blocking(move || nix::sys::signal::kill(pid, sigkill))
-                .await
-                .map_err(map_err)??;
-            Ok::<_, Error>(())
-        };
-
-        let mut system = sysinfo::System::new();
-        while !proc.is_finished() {
-            if start.elapsed() > self.config.time_limit {
-                tracing::info!("manual kill for time");
-                kill().await?;
-                status = Status::TimeLimitExceed;
-                break;
+        let clock = Clock::new(cgroup.as_ref(), pid);
+        let mut status = watch(&self.config, start, clock, &proc).await?;
+        if status != Status::Okay {
+            tracing::info!("manual kill for {status:?}");
+            match &cgroup {
+                Some(cgroup) => cgroup.kill().await?,
+                None => {
+                    let sigkill = nix::sys::signal::SIGKILL;
+                    tokio::task::spawn_blocking(move || nix::sys::signal::kill(pid, sigkill))
+                        .await
+                        .map_err(map_err)??;
+                }
             }
-            let memory = system.refresh_processes_specifics(
-                sysinfo::ProcessesToUpdate::Some(&[sysinfo::Pid::from_u32(pid.as_raw() as u32)]),
-                false,
-                sysinfo::ProcessRefreshKind::nothing().with_memory(),
-            );
-            if memory as u64 > self.config.memory_limit {
-                tracing::info!("manual kill for memory");
-                kill().await?;
-                status = Status::MemoryLimitExceed;
-                break;
-            }
-            // tokio::time::sleep(Duration::from_millis(100)).await;
         }
+        let res = proc.await.map_err(map_err)??;
+        let wall_time = start.elapsed();
 
         stdin.await.map_err(map_err)??;
-        let stdout = stdout.await.map_err(map_err)??;
+        let (stdout, mut output_exceeded) = stdout.await.map_err(map_err)??;
+        let output_file = match file_io {
+            Some(file_io) => {
+                file_io
+                    .collect_output(dir, self.config.output_limit)
+                    .await?
+            }
+            None => None,
+        };
+        let output_file = output_file.map(|(data, exceeded)| {
+            output_exceeded |= exceeded;
+            data
+        });
+        let stderr = stderr.await.map_err(map_err)??;
 
-        let res = proc.await.map_err(map_err)??;
+        let (cpu_time, memory_used) = match &cgroup {
+            Some(cgroup) => {
+                if cgroup.oom_killed().await? {
+                    status = Status::MemoryLimitExceed;
+                }
+                let memory = cgroup.memory_peak().await?.unwrap_or(res.rusage.maxrss);
+                (cgroup.cpu_time().await?, memory)
+            }
+            None => (res.rusage.utime + res.rusage.stime, res.rusage.maxrss),
+        };
 
-        if res.rusage.maxrss > self.config.memory_limit {
+        // firejail exits with 128 plus the signal that killed the program
+        let (exit_code, signal) = match (res.status.code(), res.status.signal()) {
+            (Some(code), None) if code > 128 => (None, Some(code - 128)),
+            other => other,
+        };
+        if status == Status::Okay
+            && (cpu_time > self.config.cpu_time_limit || wall_time > self.config.wall_time_limit)
+        {
+            status = Status::TimeLimitExceed;
+        }
+        if memory_used > self.config.memory_limit {
             status = Status::MemoryLimitExceed;
-        } else if res.status.code() != Some(0) && status == Status::Okay {
+        } else if output_exceeded && status == Status::Okay {
+            status = Status::OutputLimitExceeded;
+        } else if exit_code != Some(0) && status == Status::Okay {
             status = Status::RuntimeError;
         }
 
-        let wall_time = start.elapsed();
         tracing::info!("wall time {}", wall_time.as_millis());
 
         Ok(RunResult {
-            time_used: res.rusage.stime,
-            memory_used: res.rusage.maxrss,
-            exit_code: res.status.code(),
+            cpu_time,
+            wall_time,
+            memory_used,
+            exit_code,
+            signal,
             status,
             stdout,
+            output_file,
+            stderr,
         })
     }
 }
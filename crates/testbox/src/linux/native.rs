@@ -0,0 +1,788 @@
+//! A sandbox built directly on namespaces and seccomp, so no external binary is needed.
+//!
+//! The program runs under a minimal init, pid 1 of fresh user, mount, pid, net, ipc
+//! and uts namespaces.
+//! Its root is the read-only `Config::root`, with the program bind-mounted at `/prog`
+//! and the system library directories bind-mounted read-only for dynamically linked programs.
+
+use super::*;
+use cgroup::Cgroup;
+use libc::{c_char, c_int, c_ulong};
+use nix::sched::CloneFlags;
+use std::ffi::CString;
+use std::fs::File;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+
+/// Host directories made visible inside the sandbox when they exist.
+const SYSTEM_DIRS: [&str; 4] = ["/usr", "/lib", "/lib64", "/bin"];
+
+const STACK_SIZE: usize = 1 << 20;
+
+/// Writable working directory of programs doing file io and of commands.
+const WORKDIR: &str = "/work";
+
+/// Search path of commands, all inside `SYSTEM_DIRS`.
+const PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_pread64,
+    libc::SYS_pwrite64,
+    libc::SYS_lseek,
+    libc::SYS_close,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_openat,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_faccessat2,
+    libc::SYS_fcntl,
+    libc::SYS_ioctl,
+    libc::SYS_dup,
+    libc::SYS_dup3,
+    libc::SYS_getcwd,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_brk,
+    libc::SYS_madvise,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_tgkill,
+    libc::SYS_set_tid_address,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_prlimit64,
+    libc::SYS_getrandom,
+    libc::SYS_futex,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_getres,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_gettimeofday,
+    libc::SYS_nanosleep,
+    libc::SYS_times,
+    libc::SYS_getrusage,
+    libc::SYS_sysinfo,
+    libc::SYS_uname,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_sched_yield,
+    libc::SYS_execve,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_open,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_stat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_lstat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_access,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_readlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_dup2,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_arch_prctl,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_time,
+];
+
+/// Fail with `EPERM` rather than killing, so `system()` and friends just report an error.
+/// `clone` is let through for threads when they are allowed.
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_clone,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_fork,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_vfork,
+];
+
+fn seccomp_filter(threads: bool) -> Vec<libc::sock_filter> {
+    let stmt = |code: u32, k: u32| libc::sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    let jump_eq = |k: u32, jt: u8, jf: u8| libc::sock_filter {
+        code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+        jt,
+        jf,
+        k,
+    };
+    let load = |offset: usize| stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset as u32);
+    let ret = |action: u32| stmt(libc::BPF_RET | libc::BPF_K, action);
+
+    let mut filter = vec![
+        load(std::mem::offset_of!(libc::seccomp_data, arch)),
+        jump_eq(AUDIT_ARCH, 1, 0),
+        ret(libc::SECCOMP_RET_KILL_PROCESS),
+        load(std::mem::offset_of!(libc::seccomp_data, nr)),
+        // its flags are behind a pointer, so make the libc fall back to plain clone
+        jump_eq(libc::SYS_clone3 as u32, 0, 1),
+        ret(libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+    ];
+    if threads {
+        // the flags are the first argument of clone, their lower half holds CLONE_THREAD
+        filter.extend([
+            jump_eq(libc::SYS_clone as u32, 0, 4),
+            load(std::mem::offset_of!(libc::seccomp_data, args)),
+            libc::sock_filter {
+                code: (libc::BPF_JMP | libc::BPF_JSET | libc::BPF_K) as u16,
+                jt: 0,
+                jf: 1,
+                k: libc::CLONE_THREAD as u32,
+            },
+            ret(libc::SECCOMP_RET_ALLOW),
+            ret(libc::SECCOMP_RET_ERRNO | libc::EPERM as u32),
+        ]);
+    }
+    for &nr in ALLOWED_SYSCALLS {
+        filter.push(jump_eq(nr as u32, 0, 1));
+        filter.push(ret(libc::SECCOMP_RET_ALLOW));
+    }
+    for &nr in DENIED_SYSCALLS {
+        filter.push(jump_eq(nr as u32, 0, 1));
+        filter.push(ret(libc::SECCOMP_RET_ERRNO | libc::EPERM as u32));
+    }
+    filter.push(ret(libc::SECCOMP_RET_KILL_PROCESS));
+    filter
+}
+
+fn cstring(path: impl AsRef<OsStr>) -> Result<CString> {
+    CString::new(path.as_ref().as_bytes()).map_err(map_err)
+}
+
+/// Everything the child needs, prepared before the clone so it only issues raw syscalls.
+struct Child {
+    sync: RawFd,
+    report: RawFd,
+    /// Where the init passes on the wait status of the program.
+    status: RawFd,
+    stdin: RawFd,
+    stdout: RawFd,
+    stderr: RawFd,
+    root: CString,
+    /// (source, target, writable), the others are remounted read-only.
+    binds: Vec<(CString, CString, bool)>,
+    workdir: CString,
+    program: CString,
+    argv: Vec<*const c_char>,
+    envp: Vec<*const c_char>,
+    rlimits: Vec<(libc::__rlimit_resource_t, libc::rlim_t)>,
+    /// No filter is installed for trusted commands.
+    filter: Option<Vec<libc::sock_filter>>,
+}
+
+/// Steps reported back to the parent when the setup fails.
+#[derive(Debug, Clone, Copy)]
+#[repr(u8)]
+enum Step {
+    Sync,
+    Mount,
+    PivotRoot,
+    Fork,
+    Stdio,
+    Rlimit,
+    Seccomp,
+    Exec,
+}
+
+impl Child {
+    fn fail(&self, step: Step) -> ! {
+        // SAFETY: plain syscalls on fds owned by this process
+        unsafe {
+            let errno = *libc::__errno_location();
+            let mut buf = [0u8; 5];
+            buf[0] = step as u8;
+            buf[1..].copy_from_slice(&errno.to_ne_bytes());
+            libc::write(self.report, buf.as_ptr().cast(), buf.len());
+            libc::_exit(127)
+        }
+    }
+
+    fn check(&self, step: Step, ret: c_int) {
+        if ret < 0 {
+            self.fail(step);
+        }
+    }
+
+    /// Remounts a bind mount read-only, keeping the flags a user namespace may not drop.
+    unsafe fn remount_readonly(&self, target: *const c_char) {
+        unsafe {
+            let mut stat: libc::statvfs = std::mem::zeroed();
+            self.check(Step::Mount, libc::statvfs(target, &mut stat));
+            // the ST_* flags share their values with the MS_* ones
+            let locked = stat.f_flag & (libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC);
+            let flags = libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked;
+            self.check(
+                Step::Mount,
+                libc::mount(
+                    std::ptr::null(),
+                    target,
+                    std::ptr::null(),
+                    flags,
+                    std::ptr::null(),
+                ),
+            );
+        }
+    }
+
+    fn run(&self) -> isize {
+        // SAFETY: only async-signal-safe syscalls on data prepared by the parent
+        unsafe {
+            let mut buf = [0u8; 1];
+            if libc::read(self.sync, buf.as_mut_ptr().cast(), 1) != 1 {
+                self.fail(Step::Sync);
+            }
+
+            let null = std::ptr::null::<c_char>();
+            let mount = |source: *const c_char, target: *const c_char, flags: c_ulong| {
+                libc::mount(source, target, null, flags, std::ptr::null())
+            };
+            self.check(
+                Step::Mount,
+                mount(null, c"/".as_ptr(), libc::MS_REC | libc::MS_PRIVATE),
+            );
+            self.check(
+                Step::Mount,
+                mount(
+                    self.root.as_ptr(),
+                    self.root.as_ptr(),
+                    libc::MS_BIND | libc::MS_REC,
+                ),
+            );
+            for (source, target, writable) in &self.binds {
+                self.check(
+                    Step::Mount,
+                    mount(
+                        source.as_ptr(),
+                        target.as_ptr(),
+                        libc::MS_BIND | libc::MS_REC,
+                    ),
+                );
+                if !writable {
+                    self.remount_readonly(target.as_ptr());
+                }
+            }
+
+            self.check(Step::PivotRoot, libc::chdir(self.root.as_ptr()));
+            let dot = c".".as_ptr();
+            self.check(
+                Step::PivotRoot,
+                libc::syscall(libc::SYS_pivot_root, dot, dot) as c_int,
+            );
+            self.check(Step::PivotRoot, libc::umount2(dot, libc::MNT_DETACH));
+            self.check(Step::PivotRoot, libc::chdir(c"/".as_ptr()));
+            self.remount_readonly(c"/".as_ptr());
+            self.check(Step::PivotRoot, libc::chdir(self.workdir.as_ptr()));
+
+            // a plain fork, the libc one runs handlers that may wait on locks of other threads
+            let pid = libc::syscall(libc::SYS_clone, libc::SIGCHLD as c_ulong, 0, 0, 0, 0);
+            self.check(Step::Fork, pid as c_int);
+            if pid == 0 {
+                self.exec()
+            }
+            self.init(pid as libc::pid_t)
+        }
+    }
+
+    /// Stays behind as pid 1 of the namespace, which ignores signals it doesn't handle,
+    /// so the program dies of them like anywhere else. Passes on how the program ended.
+    unsafe fn init(&self, pid: libc::pid_t) -> ! {
+        unsafe {
+            // it never execs, so everything the parent had open must go, the stdin of the
+            // program reaches its end only once no write end is left
+            let status = self.status as libc::c_uint;
+            libc::syscall(libc::SYS_close_range, 3, status - 1, 0);
+            libc::syscall(libc::SYS_close_range, status + 1, libc::c_uint::MAX, 0);
+            let mut wait_status: c_int = 0;
+            while libc::waitpid(pid, &mut wait_status, 0) < 0 {
+                if *libc::__errno_location() != libc::EINTR {
+                    libc::_exit(127);
+                }
+            }
+            let bytes = wait_status.to_ne_bytes();
+            libc::write(self.status, bytes.as_ptr().cast(), bytes.len());
+            libc::_exit(0)
+        }
+    }
+
+    unsafe fn exec(&self) -> ! {
+        unsafe {
+            self.check(Step::Stdio, libc::dup2(self.stdin, 0));
+            self.check(Step::Stdio, libc::dup2(self.stdout, 1));
+            self.check(Step::Stdio, libc::dup2(self.stderr, 2));
+            // the runtime ignores SIGPIPE and that would survive the exec
+            libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+
+            for &(resource, value) in &self.rlimits {
+                let limit = libc::rlimit {
+                    rlim_cur: value,
+                    rlim_max: value,
+                };
+                self.check(Step::Rlimit, libc::setrlimit(resource, &limit));
+            }
+
+            self.check(
+                Step::Seccomp,
+                libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0),
+            );
+            if let Some(filter) = &self.filter {
+                let prog = libc::sock_fprog {
+                    len: filter.len() as u16,
+                    filter: filter.as_ptr() as *mut _,
+                };
+                self.check(
+                    Step::Seccomp,
+                    libc::syscall(
+                        libc::SYS_seccomp,
+                        libc::SECCOMP_SET_MODE_FILTER,
+                        0,
+                        &prog as *const libc::sock_fprog,
+                    ) as c_int,
+                );
+            }
+
+            libc::execve(
+                self.program.as_ptr(),
+                self.argv.as_ptr(),
+                self.envp.as_ptr(),
+            );
+            self.fail(Step::Exec)
+        }
+    }
+}
+
+/// Clones the child into fresh namespaces, it waits on `Child::sync` before setting itself up.
+fn spawn(child: Child) -> Result<nix::unistd::Pid> {
+    let mut stack = vec![0u8; STACK_SIZE];
+    let flags = CloneFlags::CLONE_NEWUSER
+        | CloneFlags::CLONE_NEWNS
+        | CloneFlags::CLONE_NEWPID
+        | CloneFlags::CLONE_NEWNET
+        | CloneFlags::CLONE_NEWIPC
+        | CloneFlags::CLONE_NEWUTS;
+    // SAFETY: the child runs on its own copy of the address space and only issues raw syscalls
+    let pid = unsafe {
+        nix::sched::clone(
+            Box::new(|| child.run()),
+            &mut stack,
+            flags,
+            Some(libc::SIGCHLD),
+        )?
+    };
+    Ok(pid)
+}
+
+fn pipe() -> Result<(OwnedFd, OwnedFd)> {
+    Ok(nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC)?)
+}
+
+#[derive(Debug, Clone)]
+pub struct NativeTestBox {
+    config: Config,
+}
+
+/// Finds a command the way a shell would, it must be visible inside the sandbox.
+fn which(program: &OsStr) -> Result<PathBuf> {
+    let path = Path::new(program);
+    let found = if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        PATH.split(':')
+            .map(|dir| Path::new(dir).join(path))
+            .find(|path| path.is_file())
+    };
+    match found {
+        Some(path) if SYSTEM_DIRS.iter().any(|dir| path.starts_with(dir)) => Ok(path),
+        _ => Err(Error::OtherError(format!(
+            "command {} not found in {PATH}",
+            path.display()
+        ))),
+    }
+}
+
+/// A program to execute inside the prepared root.
+struct Job<'a> {
+    binds: Vec<(PathBuf, PathBuf, bool)>,
+    /// Host directory mounted writable at `WORKDIR`, the program starts in `/` without one.
+    work: Option<PathBuf>,
+    /// Path inside the sandbox.
+    program: CString,
+    argv: Vec<CString>,
+    envp: Vec<CString>,
+    filter: Option<Vec<libc::sock_filter>>,
+    stdin: &'a [u8],
+}
+
+impl NativeTestBox {
+    /// Creates the mount points of the sandbox root.
+    async fn prepare_root(&self) -> Result<Vec<(PathBuf, PathBuf, bool)>> {
+        let root = &self.config.root;
+        if root.exists() {
+            fs::remove_dir_all(root).await?;
+        }
+        fs::create_dir_all(root.join("dev")).await?;
+        let mut binds = Vec::new();
+        for dir in SYSTEM_DIRS {
+            let source = Path::new(dir);
+            if !source.exists() {
+                continue;
+            }
+            let target = root.join(dir.trim_start_matches('/'));
+            fs::create_dir_all(&target).await?;
+            binds.push((source.to_path_buf(), target, false));
+        }
+        fs::write(root.join("dev/null"), b"").await?;
+        binds.push(("/dev/null".into(), root.join("dev/null"), false));
+        fs::create_dir(root.join(WORKDIR.trim_start_matches('/'))).await?;
+        Ok(binds)
+    }
+
+    /// Writes the id maps of the child, letting it act as root inside its user namespace.
+    async fn map_ids(pid: i32) -> Result<()> {
+        // SAFETY: getuid and getgid never fail
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let proc = PathBuf::from(format!("/proc/{pid}"));
+        fs::write(proc.join("setgroups"), "deny").await?;
+        fs::write(proc.join("uid_map"), format!("0 {uid} 1")).await?;
+        fs::write(proc.join("gid_map"), format!("0 {gid} 1")).await?;
+        Ok(())
+    }
+
+    /// Runs a job in the fresh namespaces and collects its result.
+    async fn execute(&self, job: Job<'_>, file_io: Option<&FileIo>) -> Result<RunResult> {
+        let Job {
+            mut binds,
+            work,
+            program,
+            argv,
+            envp,
+            filter,
+            stdin,
+        } = job;
+        if let Some(work) = &work {
+            let target = self.config.root.join(WORKDIR.trim_start_matches('/'));
+            binds.push((work.clone(), target, true));
+        }
+
+        let cgroup = match &self.config.cgroup {
+            Some(parent) => {
+                let pids = self.config.process_limit + 1;
+                Some(Cgroup::create(parent, self.config.memory_limit, pids).await?)
+            }
+            None => None,
+        };
+
+        let mut rlimits = vec![
+            (libc::RLIMIT_CPU, self.config.rlimit_cpu()),
+            (libc::RLIMIT_STACK, self.config.stack_limit),
+            (libc::RLIMIT_NOFILE, self.config.open_files_limit),
+            // counted per user namespace by recent kernels, so it bounds this run alone,
+            // where the init is one of them
+            (
+                libc::RLIMIT_NPROC,
+                self.config.process_limit as libc::rlim_t + 1,
+            ),
+        ];
+        if cgroup.is_none() {
+            rlimits.push((libc::RLIMIT_AS, self.config.rlimit_as()));
+        }
+        if work.is_some() {
+            rlimits.push((libc::RLIMIT_FSIZE, self.config.output_limit + 1));
+        }
+
+        let (sync_read, sync_write) = pipe()?;
+        let (report_read, report_write) = pipe()?;
+        let (status_read, status_write) = pipe()?;
+        let (stdin_read, stdin_write) = pipe()?;
+        let (stdout_read, stdout_write) = pipe()?;
+        let (stderr_read, stderr_write) = pipe()?;
+
+        let start = std::time::Instant::now();
+        let pid = spawn(Child {
+            sync: sync_read.as_raw_fd(),
+            report: report_write.as_raw_fd(),
+            status: status_write.as_raw_fd(),
+            stdin: stdin_read.as_raw_fd(),
+            stdout: stdout_write.as_raw_fd(),
+            stderr: stderr_write.as_raw_fd(),
+            root: cstring(&self.config.root)?,
+            binds: binds
+                .iter()
+                .map(|(source, target, writable)| {
+                    Ok((cstring(source)?, cstring(target)?, *writable))
+                })
+                .collect::<Result<_>>()?,
+            workdir: match work {
+                Some(_) => cstring(WORKDIR)?,
+                None => cstring("/")?,
+            },
+            program,
+            argv: argv
+                .iter()
+                .map(|arg| arg.as_ptr())
+                .chain(std::iter::once(std::ptr::null()))
+                .collect(),
+            envp: envp
+                .iter()
+                .map(|env| env.as_ptr())
+                .chain(std::iter::once(std::ptr::null()))
+                .collect(),
+            rlimits,
+            filter,
+        })?;
+        drop((
+            sync_read,
+            report_write,
+            status_write,
+            stdin_read,
+            stdout_write,
+            stderr_write,
+        ));
+
+        let kill = async || {
+            let sigkill = nix::sys::signal::SIGKILL;
+            tokio::task::spawn_blocking(move || nix::sys::signal::kill(pid, sigkill))
+                .await
+                .map_err(map_err)??;
+            Ok::<_, Error>(())
+        };
+        let wait = move || {
+            let mut status = 0;
+            // SAFETY: rusage is plain old data filled by the kernel
+            let mut rusage = unsafe { std::mem::zeroed::<libc::rusage>() };
+            // SAFETY: waits for our own child
+            let ret = unsafe { libc::wait4(pid.as_raw(), &mut status, 0, &mut rusage) };
+            if ret < 0 {
+                return Err(Error::Io(std::io::Error::last_os_error()));
+            }
+            Ok((status, rusage))
+        };
+
+        let setup = async {
+            Self::map_ids(pid.as_raw()).await?;
+            if let Some(cgroup) = &cgroup {
+                cgroup.attach(pid.as_raw() as u32).await?;
+            }
+            nix::unistd::write(&sync_write, b"x")?;
+            Ok::<_, Error>(())
+        };
+        if let Err(err) = setup.await {
+            kill().await?;
+            tokio::task::spawn_blocking(wait).await.map_err(map_err)??;
+            return Err(err);
+        }
+        drop(sync_write);
+
+        let report = tokio::task::spawn_blocking(move || {
+            let mut buf = Vec::new();
+            File::from(report_read).read_to_end(&mut buf)?;
+            Ok::<_, std::io::Error>(buf)
+        })
+        .await
+        .map_err(map_err)??;
+        if let [step, errno @ ..] = report.as_slice() {
+            tokio::task::spawn_blocking(wait).await.map_err(map_err)??;
+            let errno = i32::from_ne_bytes(errno.try_into().map_err(map_err)?);
+            return Err(Error::OtherError(format!(
+                "sandbox setup failed at step {step}: {}",
+                std::io::Error::from_raw_os_error(errno)
+            )));
+        }
+
+        let stdin_data = stdin.to_owned();
+        let stdin = tokio::task::spawn_blocking(move || {
+            // the program may exit without reading its input
+            let _ = File::from(stdin_write).write_all(&stdin_data);
+        });
+        let output_limit = self.config.output_limit;
+        let stdout = tokio::task::spawn_blocking(move || {
+            let ret = read_limited(File::from(stdout_read), output_limit)?;
+            if ret.1 {
+                // SIGPIPE cannot stop a program that ignores it
+                let _ = nix::sys::signal::kill(pid, nix::sys::signal::SIGKILL);
+            }
+            Ok::<_, std::io::Error>(ret)
+        });
+        let stderr = tokio::task::spawn_blocking(move || read_stderr(File::from(stderr_read)));
+
+        let proc = tokio::task::spawn_blocking(wait);
+        let clock = Clock::new(cgroup.as_ref(), pid);
+        let mut status = watch(&self.config, start, clock, &proc).await?;
+        if status != Status::Okay {
+            tracing::info!("manual kill for {status:?}");
+            match &cgroup {
+                Some(cgroup) => cgroup.kill().await?,
+                None => kill().await?,
+            }
+        }
+        let (init_status, rusage) = proc.await.map_err(map_err)??;
+        let passed = tokio::task::spawn_blocking(move || {
+            let mut buf = Vec::new();
+            File::from(status_read).read_to_end(&mut buf)?;
+            Ok::<_, std::io::Error>(buf)
+        })
+        .await
+        .map_err(map_err)??;
+        // nothing is passed on when the init is killed along with the program
+        let wait_status = match <[u8; 4]>::try_from(passed.as_slice()) {
+            Ok(bytes) => c_int::from_ne_bytes(bytes),
+            Err(_) => init_status,
+        };
+        let wall_time = start.elapsed();
+
+        stdin.await.map_err(map_err)?;
+        let (stdout, mut output_exceeded) = stdout.await.map_err(map_err)??;
+        let output_file = match (file_io, &work) {
+            (Some(file_io), Some(work)) => {
+                file_io
+                    .collect_output(work, self.config.output_limit)
+                    .await?
+            }
+            _ => None,
+        };
+        let output_file = output_file.map(|(data, exceeded)| {
+            output_exceeded |= exceeded;
+            data
+        });
+        let stderr = stderr.await.map_err(map_err)??;
+
+        let timeval = |tv: libc::timeval| {
+            Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+        };
+        let maxrss = rusage.ru_maxrss as u64 * 1024;
+        let (cpu_time, memory_used) = match &cgroup {
+            Some(cgroup) => {
+                if cgroup.oom_killed().await? {
+                    status = Status::MemoryLimitExceed;
+                }
+                let memory = cgroup.memory_peak().await?.unwrap_or(maxrss);
+                (cgroup.cpu_time().await?, memory)
+            }
+            None => (timeval(rusage.ru_utime) + timeval(rusage.ru_stime), maxrss),
+        };
+
+        let exit_code = libc::WIFEXITED(wait_status).then(|| libc::WEXITSTATUS(wait_status));
+        let signal = libc::WIFSIGNALED(wait_status).then(|| libc::WTERMSIG(wait_status));
+        if status == Status::Okay
+            && (cpu_time > self.config.cpu_time_limit || wall_time > self.config.wall_time_limit)
+        {
+            status = Status::TimeLimitExceed;
+        }
+        if memory_used > self.config.memory_limit {
+            status = Status::MemoryLimitExceed;
+        } else if output_exceeded && status == Status::Okay {
+            status = Status::OutputLimitExceeded;
+        } else if exit_code != Some(0) && status == Status::Okay {
+            status = Status::RuntimeError;
+        }
+
+        tracing::info!("wall time {}", wall_time.as_millis());
+
+        Ok(RunResult {
+            cpu_time,
+            wall_time,
+            memory_used,
+            exit_code,
+            signal,
+            status,
+            stdout,
+            output_file,
+            stderr,
+        })
+    }
+}
+
+impl TestBox for NativeTestBox {
+    async fn new(config: &Config) -> Result<Self> {
+        Ok(NativeTestBox {
+            config: config.clone(),
+        })
+    }
+
+    async fn run_single<'a>(
+        &self,
+        path: impl AsRef<Path>,
+        args: impl IntoIterator<Item = &'a OsStr>,
+        stdin: impl AsRef<[u8]>,
+    ) -> Result<RunResult> {
+        let mut binds = self.prepare_root().await?;
+        let prog = fs::canonicalize(path.as_ref()).await?;
+        fs::write(self.config.root.join("prog"), b"").await?;
+        binds.push((prog, self.config.root.join("prog"), false));
+
+        let stdin = stdin.as_ref();
+        let (work, stdin) = match &self.config.file_io {
+            Some(file_io) => {
+                // the only place the program may write to, its input and output files live here
+                let work = self.config.root.join(WORKDIR.trim_start_matches('/'));
+                file_io.place_input(&work, stdin).await?;
+                (Some(work), &[][..])
+            }
+            None => (None, stdin),
+        };
+
+        let argv = std::iter::once(cstring("prog")?)
+            .chain(args.into_iter().map(cstring).collect::<Result<Vec<_>>>()?)
+            .collect();
+        let job = Job {
+            binds,
+            work,
+            program: cstring("/prog")?,
+            argv,
+            envp: Vec::new(),
+            filter: Some(seccomp_filter(self.config.process_limit > 1)),
+            stdin,
+        };
+        self.execute(job, self.config.file_io.as_ref()).await
+    }
+
+    async fn run_command<'a>(
+        &self,
+        dir: impl AsRef<Path>,
+        program: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item = &'a OsStr>,
+    ) -> Result<RunResult> {
+        let binds = self.prepare_root().await?;
+        let program = program.as_ref();
+        let path = which(program)?;
+
+        let argv = std::iter::once(cstring(program)?)
+            .chain(args.into_iter().map(cstring).collect::<Result<Vec<_>>>()?)
+            .collect();
+        let job = Job {
+            binds,
+            work: Some(fs::canonicalize(dir.as_ref()).await?),
+            program: cstring(path)?,
+            argv,
+            envp: vec![
+                cstring(format!("PATH={PATH}"))?,
+                cstring(format!("TMPDIR={WORKDIR}"))?,
+            ],
+            filter: None,
+            stdin: &[],
+        };
+        self.execute(job, None).await
+    }
+}
@@ -0,0 +1,92 @@
+use super::*;
+use std::time::Instant;
+use tokio::task::JoinHandle;
+
+/// Where the usage of a running program is sampled from.
+pub enum Clock<'a> {
+    Cgroup(&'a Cgroup),
+    /// Sums up a process and all of its descendants.
+    Tree(sysinfo::Pid, Box<sysinfo::System>),
+}
+
+impl<'a> Clock<'a> {
+    pub fn new(cgroup: Option<&'a Cgroup>, pid: nix::unistd::Pid) -> Self {
+        match cgroup {
+            Some(cgroup) => Clock::Cgroup(cgroup),
+            None => Clock::Tree(
+                sysinfo::Pid::from_u32(pid.as_raw() as u32),
+                Box::new(sysinfo::System::new()),
+            ),
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        match self {
+            Clock::Cgroup(_) => Duration::from_millis(10),
+            // scanning every process is much heavier than reading a cgroup file
+            Clock::Tree(..) => Duration::from_millis(50),
+        }
+    }
+
+    /// Cpu time and memory used so far.
+    async fn sample(&mut self) -> Result<(Duration, u64)> {
+        match self {
+            // the kernel enforces the memory limit of a cgroup by itself
+            Clock::Cgroup(cgroup) => Ok((cgroup.cpu_time().await?, 0)),
+            Clock::Tree(root, system) => {
+                system.refresh_processes_specifics(
+                    sysinfo::ProcessesToUpdate::All,
+                    true,
+                    sysinfo::ProcessRefreshKind::nothing()
+                        .with_cpu()
+                        .with_memory(),
+                );
+                let processes = system.processes();
+                let in_tree = |mut pid: sysinfo::Pid| {
+                    loop {
+                        if pid == *root {
+                            return true;
+                        }
+                        match processes.get(&pid).and_then(|proc| proc.parent()) {
+                            Some(parent) => pid = parent,
+                            None => return false,
+                        }
+                    }
+                };
+                let (mut cpu, mut memory) = (0, 0);
+                for (pid, proc) in processes {
+                    // a thread shares the usage of its process, which already sums it up
+                    if proc.thread_kind().is_none() && in_tree(*pid) {
+                        cpu += proc.accumulated_cpu_time();
+                        memory += proc.memory();
+                    }
+                }
+                Ok((Duration::from_millis(cpu), memory))
+            }
+        }
+    }
+}
+
+/// Polls a program until it exits or breaks a limit, killing it is left to the caller.
+pub async fn watch<T>(
+    config: &Config,
+    start: Instant,
+    mut clock: Clock<'_>,
+    proc: &JoinHandle<T>,
+) -> Result<Status> {
+    while !proc.is_finished() {
+        let wall = start.elapsed();
+        let (cpu, memory) = clock.sample().await?;
+        if cpu > config.cpu_time_limit || wall > config.wall_time_limit {
+            return Ok(Status::TimeLimitExceed);
+        }
+        if memory > config.memory_limit {
+            return Ok(Status::MemoryLimitExceed);
+        }
+        if wall.saturating_sub(cpu) > config.idle_limit {
+            return Ok(Status::IdleLimitExceeded);
+        }
+        tokio::time::sleep(clock.interval()).await;
+    }
+    Ok(Status::Okay)
+}
@@ -0,0 +1,8 @@
+#include <cstdlib>
+#include <iostream>
+using namespace std;
+
+int main() {
+    cout << "aborting" << endl;
+    abort();
+}
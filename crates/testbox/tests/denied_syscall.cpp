@@ -0,0 +1,18 @@
+#include <sys/syscall.h>
+#include <unistd.h>
+#include <cerrno>
+#include <iostream>
+using namespace std;
+
+int main() {
+    errno = 0;
+    long ret = syscall(SYS_clone3, nullptr, 0);
+    cout << "clone3 " << ret << " " << (errno == ENOSYS ? "ENOSYS" : "other") << endl;
+    errno = 0;
+    pid_t pid = fork();
+    if (pid == 0) {
+        _exit(0);
+    }
+    cout << "fork " << pid << " " << (errno == EPERM ? "EPERM" : "other") << endl;
+    return 0;
+}
@@ -0,0 +1,10 @@
+#include <bits/stdc++.h>
+using namespace std;
+
+int main() {
+    int a;
+    cin >> a;
+    volatile int b = 100;
+    cout << b / a << endl;
+    return 0;
+}
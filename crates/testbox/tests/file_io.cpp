@@ -0,0 +1,11 @@
+#include <bits/stdc++.h>
+using namespace std;
+
+int main() {
+    freopen("sum.in", "r", stdin);
+    freopen("sum.out", "w", stdout);
+    int a, b;
+    cin >> a >> b;
+    cout << a + b << endl;
+    return 0;
+}
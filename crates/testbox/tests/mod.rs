@@ -20,7 +20,15 @@ async fn run(code: &str, memory: u64, time: u64, stdin: &str) -> RunResult {
     let testbox = PlatformTestBox::new(&Config {
         root: "testbox".into(),
         memory_limit: memory << 20,
-        time_limit: Duration::from_millis(time),
+        cpu_time_limit: Duration::from_millis(time),
+        wall_time_limit: Duration::from_millis(time * 3),
+        idle_limit: Duration::from_millis(time * 2),
+        output_limit: 1 << 20,
+        stack_limit: memory << 20,
+        process_limit: 1,
+        open_files_limit: 64,
+        file_io: None,
+        cgroup: None,
     })
     .await
     .unwrap();
@@ -70,3 +78,27 @@ async fn timeout() {
     println!("{:?}", out);
     assert_eq!(out.status, Status::TimeLimitExceed);
 }
+
+#[tokio::test]
+async fn output_limit() {
+    let out = run(include_str!("output.cpp"), 100, 1000, "").await;
+    println!("{:?}", out.status);
+    assert_eq!(out.status, Status::OutputLimitExceeded);
+    assert_eq!(out.stdout.len(), 1 << 20);
+    assert_eq!(out.stderr.as_slice(), "start printing\n".as_bytes());
+}
+
+#[tokio::test]
+async fn idle() {
+    let out = run(include_str!("sleep.cpp"), 100, 1000, "").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::IdleLimitExceeded);
+}
+
+#[tokio::test]
+async fn divide_by_zero() {
+    let out = run(include_str!("divide.cpp"), 100, 1000, "0").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::RuntimeError);
+    assert_eq!(out.signal, Some(8));
+}
@@ -0,0 +1,320 @@
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+use testbox::*;
+
+fn compile(name: &str, code: &str) -> String {
+    let dir = format!("tmp/native/{name}");
+    if fs::exists(&dir).unwrap() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(format!("{dir}/prog.cpp"), code).unwrap();
+    let status = Command::new("g++")
+        .arg(format!("{dir}/prog.cpp"))
+        .arg("-o")
+        .arg(format!("{dir}/prog"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    dir
+}
+
+fn config(dir: &str, memory: u64, time: u64) -> Config {
+    Config {
+        root: format!("{dir}/root").into(),
+        memory_limit: memory << 20,
+        cpu_time_limit: Duration::from_millis(time),
+        wall_time_limit: Duration::from_millis(time * 3),
+        idle_limit: Duration::from_millis(time * 2),
+        output_limit: 1 << 20,
+        stack_limit: memory << 20,
+        process_limit: 1,
+        open_files_limit: 64,
+        file_io: None,
+        cgroup: None,
+    }
+}
+
+async fn run(name: &str, code: &str, memory: u64, time: u64, stdin: &str) -> RunResult {
+    let dir = compile(name, code);
+    let testbox = NativeTestBox::new(&config(&dir, memory, time))
+        .await
+        .unwrap();
+    testbox
+        .run_single(format!("{dir}/prog"), None, stdin)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn native_normal() {
+    let out = run("normal", include_str!("normal.cpp"), 20, 1000, "1 2").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    assert_eq!(out.stdout.as_slice(), "3\n".as_bytes());
+}
+
+#[tokio::test]
+async fn native_vector_memory_enough() {
+    let out = run(
+        "vector_memory_enough",
+        include_str!("vector_memory.cpp"),
+        128,
+        1000,
+        "100",
+    )
+    .await;
+    println!("stdout {}", String::from_utf8_lossy(&out.stdout));
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    assert_eq!(out.exit_code, Some(0));
+}
+
+#[tokio::test]
+async fn native_vector_memory_out() {
+    let out = run(
+        "vector_memory_out",
+        include_str!("vector_memory.cpp"),
+        128,
+        1000,
+        "200",
+    )
+    .await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::MemoryLimitExceed);
+}
+
+#[tokio::test]
+async fn native_timeout() {
+    let out = run("timeout", include_str!("timeout.cpp"), 100, 4, "").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::TimeLimitExceed);
+}
+
+#[tokio::test]
+async fn native_output_limit() {
+    let out = run("output_limit", include_str!("output.cpp"), 100, 1000, "").await;
+    println!("{:?}", out.status);
+    assert_eq!(out.status, Status::OutputLimitExceeded);
+    assert_eq!(out.stdout.len(), 1 << 20);
+    assert_eq!(out.stderr.as_slice(), "start printing\n".as_bytes());
+}
+
+#[tokio::test]
+async fn native_idle() {
+    let out = run("idle", include_str!("sleep.cpp"), 100, 1000, "").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::IdleLimitExceeded);
+    assert!(out.cpu_time < Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn native_divide_by_zero() {
+    let out = run("divide_by_zero", include_str!("divide.cpp"), 100, 1000, "0").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::RuntimeError);
+    assert_eq!(out.signal, Some(8));
+    assert_eq!(out.exit_code, None);
+}
+
+#[tokio::test]
+async fn native_abort() {
+    let out = run("abort", include_str!("abort.cpp"), 100, 1000, "").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::RuntimeError);
+    assert_eq!(out.signal, Some(libc::SIGABRT));
+    assert_eq!(out.stdout.as_slice(), "aborting\n".as_bytes());
+}
+
+#[tokio::test]
+async fn native_file_io() {
+    let dir = compile("file_io", include_str!("file_io.cpp"));
+    let config = Config {
+        file_io: Some(FileIo {
+            input: "sum.in".into(),
+            output: "sum.out".into(),
+        }),
+        ..config(&dir, 100, 1000)
+    };
+    let testbox = NativeTestBox::new(&config).await.unwrap();
+    let out = testbox
+        .run_single(format!("{dir}/prog"), None, "1 2")
+        .await
+        .unwrap();
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    assert!(out.stdout.is_empty());
+    assert_eq!(out.output_file.as_deref(), Some("3\n".as_bytes()));
+}
+
+fn command_dir(name: &str) -> String {
+    let dir = format!("tmp/native/{name}");
+    if fs::exists(&dir).unwrap() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(format!("{dir}/work")).unwrap();
+    fs::write(format!("{dir}/work/prog.cpp"), include_str!("normal.cpp")).unwrap();
+    dir
+}
+
+async fn run_gcc(dir: &str, config: &Config) -> RunResult {
+    let testbox = NativeTestBox::new(config).await.unwrap();
+    let args = ["prog.cpp", "-o", "prog"].map(std::ffi::OsStr::new);
+    testbox
+        .run_command(format!("{dir}/work"), "g++", args)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn native_command() {
+    let dir = command_dir("command");
+    let config = Config {
+        output_limit: 64 << 20,
+        process_limit: 16,
+        ..config(&dir, 1024, 10000)
+    };
+    let out = run_gcc(&dir, &config).await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+
+    let out = NativeTestBox::new(&config)
+        .await
+        .unwrap()
+        .run_single(format!("{dir}/work/prog"), None, "1 2")
+        .await
+        .unwrap();
+    assert_eq!(out.stdout.as_slice(), "3\n".as_bytes());
+}
+
+#[tokio::test]
+async fn native_command_timeout() {
+    let dir = command_dir("command_timeout");
+    let config = Config {
+        output_limit: 64 << 20,
+        process_limit: 16,
+        ..config(&dir, 1024, 10)
+    };
+    let out = run_gcc(&dir, &config).await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::TimeLimitExceed);
+}
+
+#[tokio::test]
+async fn native_threads() {
+    let dir = compile("threads", include_str!("threads.cpp"));
+    let config = Config {
+        process_limit: 4,
+        ..config(&dir, 100, 1000)
+    };
+    let testbox = NativeTestBox::new(&config).await.unwrap();
+    let out = testbox
+        .run_single(format!("{dir}/prog"), None, "")
+        .await
+        .unwrap();
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    assert_eq!(out.stdout.as_slice(), "3\n".as_bytes());
+}
+
+#[tokio::test]
+async fn native_threads_denied() {
+    let out = run("threads_denied", include_str!("threads.cpp"), 100, 1000, "").await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::RuntimeError);
+}
+
+#[tokio::test]
+async fn native_threads_fork_denied() {
+    let dir = compile("threads_fork_denied", include_str!("denied_syscall.cpp"));
+    let config = Config {
+        process_limit: 4,
+        ..config(&dir, 100, 1000)
+    };
+    let out = NativeTestBox::new(&config)
+        .await
+        .unwrap()
+        .run_single(format!("{dir}/prog"), None, "")
+        .await
+        .unwrap();
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "clone3 -1 ENOSYS\nfork -1 EPERM\n"
+    );
+}
+
+#[tokio::test]
+async fn native_deep_recursion() {
+    let out = run(
+        "deep_recursion",
+        include_str!("recursion.cpp"),
+        256,
+        1000,
+        "100000",
+    )
+    .await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+}
+
+#[tokio::test]
+async fn native_socket() {
+    let out = run("socket", include_str!("socket.cpp"), 100, 1000, "").await;
+    println!("{:?}", out);
+    assert!(!out.stdout.starts_with(b"connected"));
+    // socket is not on the allow list, so the program is killed for calling it
+    assert_eq!(out.status, Status::RuntimeError);
+    assert_eq!(out.signal, Some(libc::SIGSYS));
+}
+
+#[tokio::test]
+async fn native_write_outside() {
+    let dir = compile("write_outside", include_str!("write_outside.cpp"));
+    let config = Config {
+        file_io: Some(FileIo {
+            input: "in".into(),
+            output: "out".into(),
+        }),
+        ..config(&dir, 100, 1000)
+    };
+    let out = NativeTestBox::new(&config)
+        .await
+        .unwrap()
+        .run_single(format!("{dir}/prog"), None, "")
+        .await
+        .unwrap();
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    let expected = "denied /escape\ndenied /usr/escape\ndenied /tmp/escape\ndenied /prog\ndenied /work/../escape\n";
+    assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+    assert!(!fs::exists(format!("{dir}/root/escape")).unwrap());
+}
+
+#[tokio::test]
+async fn native_denied_syscall() {
+    let out = run(
+        "denied_syscall",
+        include_str!("denied_syscall.cpp"),
+        100,
+        1000,
+        "",
+    )
+    .await;
+    println!("{:?}", out);
+    assert_eq!(out.status, Status::Okay);
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "clone3 -1 ENOSYS\nfork -1 EPERM\n"
+    );
+}
+
+#[tokio::test]
+async fn native_ptrace() {
+    let out = run("ptrace", include_str!("ptrace.cpp"), 100, 1000, "").await;
+    println!("{:?}", out);
+    assert!(out.stdout.is_empty());
+    assert_eq!(out.signal, Some(libc::SIGSYS));
+}
@@ -0,0 +1,8 @@
+#include <bits/stdc++.h>
+using namespace std;
+
+int main() {
+    cerr << "start printing" << endl;
+    while (true) { cout << "output limit exceeded\n"; }
+    return 0;
+}
@@ -0,0 +1,9 @@
+#include <sys/ptrace.h>
+#include <iostream>
+using namespace std;
+
+int main() {
+    long ret = ptrace(PTRACE_TRACEME, 0, nullptr, nullptr);
+    cout << "ptrace " << ret << endl;
+    return 0;
+}
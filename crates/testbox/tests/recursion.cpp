@@ -0,0 +1,16 @@
+#include <bits/stdc++.h>
+using namespace std;
+
+int dive(int depth) {
+    volatile char frame[1024];
+    frame[0] = depth;
+    if (depth == 0) return frame[0];
+    return dive(depth - 1) + frame[0];
+}
+
+int main() {
+    int n;
+    cin >> n;
+    cout << dive(n) << endl;
+    return 0;
+}
@@ -0,0 +1,7 @@
+#include <bits/stdc++.h>
+using namespace std;
+
+int main() {
+    this_thread::sleep_for(chrono::seconds(10));
+    return 0;
+}
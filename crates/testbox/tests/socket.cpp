@@ -0,0 +1,19 @@
+#include <arpa/inet.h>
+#include <sys/socket.h>
+#include <unistd.h>
+#include <iostream>
+using namespace std;
+
+int main() {
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    sockaddr_in addr{};
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(80);
+    inet_pton(AF_INET, "1.1.1.1", &addr.sin_addr);
+    if (fd >= 0 && connect(fd, (sockaddr *)&addr, sizeof(addr)) == 0) {
+        cout << "connected" << endl;
+    } else {
+        cout << "failed" << endl;
+    }
+    return 0;
+}
@@ -0,0 +1,13 @@
+#include <bits/stdc++.h>
+using namespace std;
+
+int main() {
+    atomic<int> sum{0};
+    vector<thread> threads;
+    for (int i = 1; i <= 2; i++) {
+        threads.emplace_back([&sum, i] { sum += i; });
+    }
+    for (auto &t : threads) t.join();
+    cout << sum << endl;
+    return 0;
+}
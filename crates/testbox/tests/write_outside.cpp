@@ -0,0 +1,13 @@
+#include <fcntl.h>
+#include <unistd.h>
+#include <iostream>
+using namespace std;
+
+int main() {
+    const char *paths[] = {"/escape", "/usr/escape", "/tmp/escape", "/prog", "/work/../escape"};
+    for (const char *path : paths) {
+        int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0644);
+        cout << (fd >= 0 ? "written " : "denied ") << path << endl;
+    }
+    return 0;
+}
@@ -1,14 +1,18 @@
 import os
 import sqlite3
 
+MIGRATIONS = "crates/server/src/sql/migrations"
+
 os.makedirs("tmp", exist_ok=True)
-os.remove("tmp/empty.db")
+if os.path.exists("tmp/empty.db"):
+    os.remove("tmp/empty.db")
 
 con = sqlite3.connect("./tmp/empty.db")
 
-with open("crates/server/src/sql/create.sql") as f:
-    sql = f.read()
-    con.executescript(sql)
+# the same schema the server reaches after applying every migration
+for name in sorted(os.listdir(MIGRATIONS)):
+    with open(os.path.join(MIGRATIONS, name)) as f:
+        con.executescript(f.read())
 
 con.commit()
 con.close()